@@ -11,11 +11,22 @@ use std::{
 
 use approx::{AbsDiffEq, RelativeEq};
 use num_traits::PrimInt;
-use structr::{Parse, ParseError, ParseErrorKind, Parser};
+use structr::{Parse, ParseError, ParseErrorKind, Parser, Write};
 
-const VERSION: [u8; 3] = [0, 7, 1];
+pub(crate) const VERSION: [u8; 3] = [0, 7, 1];
 
-#[derive(Debug, Clone, Parse)]
+/// A `#[repr]` integer read from a `.tdbin` that didn't match any of an
+/// enum's known named variants, e.g. a `MaterialKind` a newer game version
+/// introduced. Carries the enum's name alongside the raw value so a caller
+/// folding this into an `Unknown` variant (rather than failing the parse)
+/// doesn't lose track of what didn't match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReprError {
+    pub enum_name: &'static str,
+    pub raw: u32,
+}
+
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Scene<'a> {
     #[structr(eq = "Scene::MAGIC")]
     magic: [u8; 5],
@@ -48,9 +59,21 @@ impl<'a> Scene<'a> {
     pub fn iter_entities(&'a self) -> impl Iterator<Item = &'a Entity> {
         self.entities.iter().flat_map(Entity::self_and_all_children)
     }
+
+    /// Serializes back to the `.tdbin` byte layout this was parsed from.
+    /// Reproduces the original buffer exactly, except where a field backed
+    /// by a `HashMap` (`Registry`, `Tags`, `LuaTable`) had more than one
+    /// entry: their original key order isn't preserved by parsing, so those
+    /// entries may come back out in a different order.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write(&mut out);
+        out
+    }
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Fire {
     pub entity_handle: u32,
     pub pos: [f32; 3],
@@ -62,7 +85,7 @@ pub struct Fire {
 pub mod light {
     use super::*;
 
-    #[derive(Debug, Clone, Parse)]
+    #[derive(Debug, Clone, Parse, Write)]
     pub struct Light<'a> {
         pub z_u8_start: u8,
         pub kind: Kind,
@@ -84,13 +107,58 @@ pub mod light {
         pub glare: f32,
     }
 
-    #[derive(Debug, Clone, PartialEq, Eq, Parse)]
-    #[repr(u8)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     pub enum Kind {
-        Sphere = 1,
-        Capsule = 2,
-        Cone = 3,
-        Area = 4,
+        Sphere,
+        Capsule,
+        Cone,
+        Area,
+        /// See [`ReprError`].
+        Unknown(u32),
+    }
+
+    impl Kind {
+        fn from_repr(raw: u32) -> Result<Self, ReprError> {
+            Ok(match raw {
+                1 => Self::Sphere,
+                2 => Self::Capsule,
+                3 => Self::Cone,
+                4 => Self::Area,
+                other => {
+                    return Err(ReprError {
+                        enum_name: "LightKind",
+                        raw: other,
+                    })
+                }
+            })
+        }
+    }
+
+    impl<'p> Parse<'p> for Kind {
+        fn parse<'a>(parser: &'a mut Parser<'p>) -> Result<Self, ParseError<'p>>
+        where 'p: 'a {
+            let raw: u8 = parser.parse()?;
+            Ok(Self::from_repr(u32::from(raw)).unwrap_or_else(|err| Self::Unknown(err.raw)))
+        }
+    }
+
+    impl Kind {
+        fn to_repr(&self) -> u32 {
+            match self {
+                Self::Sphere => 1,
+                Self::Capsule => 2,
+                Self::Cone => 3,
+                Self::Area => 4,
+                Self::Unknown(raw) => *raw,
+            }
+        }
+    }
+
+    impl Write for Kind {
+        fn write(&self, out: &mut Vec<u8>) {
+            #[allow(clippy::cast_possible_truncation)]
+            (self.to_repr() as u8).write(out);
+        }
     }
 }
 pub use light::{Kind as LightKind, Light};
@@ -98,7 +166,7 @@ pub use light::{Kind as LightKind, Light};
 pub mod joint {
     use super::*;
 
-    #[derive(Debug, Clone, Parse)]
+    #[derive(Debug, Clone, Parse, Write)]
     pub struct Joint {
         pub kind: JointKind,
         pub shape_handles: [u32; 2],
@@ -118,16 +186,60 @@ pub mod joint {
         pub rope: Option<Rope>,
     }
 
-    #[derive(Debug, Clone, PartialEq, Eq, Parse)]
-    #[repr(u32)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     pub enum Kind {
-        Ball = 1,
-        Hinge = 2,
-        Prismatic = 3,
-        Rope = 4,
+        Ball,
+        Hinge,
+        Prismatic,
+        Rope,
+        /// See [`ReprError`].
+        Unknown(u32),
+    }
+
+    impl Kind {
+        fn from_repr(raw: u32) -> Result<Self, ReprError> {
+            Ok(match raw {
+                1 => Self::Ball,
+                2 => Self::Hinge,
+                3 => Self::Prismatic,
+                4 => Self::Rope,
+                other => {
+                    return Err(ReprError {
+                        enum_name: "JointKind",
+                        raw: other,
+                    })
+                }
+            })
+        }
     }
 
-    #[derive(Debug, Clone, Parse)]
+    impl<'p> Parse<'p> for Kind {
+        fn parse<'a>(parser: &'a mut Parser<'p>) -> Result<Self, ParseError<'p>>
+        where 'p: 'a {
+            let raw: u32 = parser.parse()?;
+            Ok(Self::from_repr(raw).unwrap_or_else(|err| Self::Unknown(err.raw)))
+        }
+    }
+
+    impl Kind {
+        fn to_repr(&self) -> u32 {
+            match self {
+                Self::Ball => 1,
+                Self::Hinge => 2,
+                Self::Prismatic => 3,
+                Self::Rope => 4,
+                Self::Unknown(raw) => *raw,
+            }
+        }
+    }
+
+    impl Write for Kind {
+        fn write(&self, out: &mut Vec<u8>) {
+            self.to_repr().write(out);
+        }
+    }
+
+    #[derive(Debug, Clone, Parse, Write)]
     pub struct Rope {
         pub rgba: Rgba,
         pub float: f32,
@@ -139,7 +251,7 @@ pub mod joint {
         pub knots: Vec<Knot>,
     }
 
-    #[derive(Debug, Clone, Parse)]
+    #[derive(Debug, Clone, Parse, Write)]
     pub struct Knot {
         pub from: [f32; 3],
         pub to: [f32; 3],
@@ -147,7 +259,7 @@ pub mod joint {
 }
 pub use joint::{Joint, Kind as JointKind, Knot, Rope};
 
-#[derive(Debug, Default, Clone, Parse)]
+#[derive(Debug, Default, Clone, Parse, Write)]
 pub struct Material {
     pub kind: MaterialKind,
     pub rgba: Rgba,
@@ -170,27 +282,29 @@ impl Hash for Material {
     }
 }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Parse)]
-#[repr(u8)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum MaterialKind {
-    None = 0,
-    Glass = 1,
-    Wood = 2,
+    None,
+    Glass,
+    Wood,
     /// Also known as concrete and brick
-    Masonry = 3,
-    Plaster = 4,
+    Masonry,
+    Plaster,
     /// Also known as weak metal
-    Metal = 5,
-    HeavyMetal = 6,
-    Rock = 7,
-    Dirt = 8,
+    Metal,
+    HeavyMetal,
+    Rock,
+    Dirt,
     /// Also known as grass
-    Foliage = 9,
-    Plastic = 10,
-    HardMetal = 11,
-    HardMasonry = 12,
-    Unknown13 = 13,
-    Unphysical = 14,
+    Foliage,
+    Plastic,
+    HardMetal,
+    HardMasonry,
+    Unknown13,
+    Unphysical,
+    /// A `kind` byte this build doesn't recognize, kept instead of failing
+    /// the whole parse. See [`ReprError`].
+    Unknown(u32),
 }
 
 impl Default for MaterialKind {
@@ -199,6 +313,72 @@ impl Default for MaterialKind {
     }
 }
 
+impl MaterialKind {
+    fn from_repr(raw: u32) -> Result<Self, ReprError> {
+        Ok(match raw {
+            0 => Self::None,
+            1 => Self::Glass,
+            2 => Self::Wood,
+            3 => Self::Masonry,
+            4 => Self::Plaster,
+            5 => Self::Metal,
+            6 => Self::HeavyMetal,
+            7 => Self::Rock,
+            8 => Self::Dirt,
+            9 => Self::Foliage,
+            10 => Self::Plastic,
+            11 => Self::HardMetal,
+            12 => Self::HardMasonry,
+            13 => Self::Unknown13,
+            14 => Self::Unphysical,
+            other => {
+                return Err(ReprError {
+                    enum_name: "MaterialKind",
+                    raw: other,
+                })
+            }
+        })
+    }
+}
+
+impl<'p> Parse<'p> for MaterialKind {
+    fn parse<'a>(parser: &'a mut Parser<'p>) -> Result<Self, ParseError<'p>>
+    where 'p: 'a {
+        let raw: u8 = parser.parse()?;
+        Ok(Self::from_repr(u32::from(raw)).unwrap_or_else(|err| Self::Unknown(err.raw)))
+    }
+}
+
+impl MaterialKind {
+    fn to_repr(&self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Glass => 1,
+            Self::Wood => 2,
+            Self::Masonry => 3,
+            Self::Plaster => 4,
+            Self::Metal => 5,
+            Self::HeavyMetal => 6,
+            Self::Rock => 7,
+            Self::Dirt => 8,
+            Self::Foliage => 9,
+            Self::Plastic => 10,
+            Self::HardMetal => 11,
+            Self::HardMasonry => 12,
+            Self::Unknown13 => 13,
+            Self::Unphysical => 14,
+            Self::Unknown(raw) => *raw,
+        }
+    }
+}
+
+impl Write for MaterialKind {
+    fn write(&self, out: &mut Vec<u8>) {
+        #[allow(clippy::cast_possible_truncation)]
+        (self.to_repr() as u8).write(out);
+    }
+}
+
 pub struct SelfAndChildrenIter<'a> {
     entity: &'a Entity<'a>,
     returned_self: bool,
@@ -235,9 +415,9 @@ pub struct Entity<'a> {
     pub handle: u32,
     pub tags: Tags<'a>,
     pub desc: &'a str,
-    #[structr(parse = "EntityKind::parse(parser, kind_byte.into())")]
+    #[structr(parse = "EntityKind::parse(parser, kind_byte)")]
     pub kind: EntityKind<'a>,
-    #[structr(len = "u32")]
+    #[structr(parse = "entity_children(parser, &kind)")]
     pub children: Vec<Entity<'a>>,
     #[structr(eq = "[0xef, 0xbe,0xef, 0xbeu8]")]
     beef_beef: [u8; 4],
@@ -250,22 +430,76 @@ impl<'a> Entity<'a> {
     }
 }
 
-impl From<u8> for EntityKindVariants {
-    fn from(byte: u8) -> Self {
-        match byte {
-            2 => Self::Shape,
-            1 => Self::Body,
-            10 => Self::Screen,
-            5 => Self::Water,
-            8 => Self::Vehicle,
-            11 => Self::Trigger,
-            4 => Self::Location,
-            9 => Self::Wheel,
-            7 => Self::Joint,
-            12 => Self::Script,
-            3 => Self::Light,
-            // other => Self::Body,
-            other => unimplemented!("entity {}", other),
+impl Write for Entity<'_> {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.kind_byte.write(out);
+        self.handle.write(out);
+        self.tags.write(out);
+        self.desc.write(out);
+        self.kind.write(out);
+        // Mirrors entity_children: an Unknown kind's children, if it had any,
+        // are already part of its raw bytes, so no count prefix is written.
+        if !matches!(self.kind, EntityKind::Unknown { .. }) {
+            #[allow(clippy::cast_possible_truncation)]
+            (self.children.len() as u32).write(out);
+            for child in &self.children {
+                child.write(out);
+            }
+        }
+        self.beef_beef.write(out);
+    }
+}
+
+/// Parses [`Entity::children`], except when `kind` is
+/// [`EntityKind::Unknown`]: an unrecognized kind's body couldn't be parsed
+/// field-by-field, so there's no way to tell where it ends and a
+/// length-prefixed `children` array would begin. Such an entity is treated
+/// as childless; [`EntityKind::parse`]'s lenient-mode resync already folded
+/// any actual children it had into `Unknown::raw`.
+fn entity_children<'p>(
+    parser: &mut Parser<'p>,
+    kind: &EntityKind<'p>,
+) -> Result<Vec<Entity<'p>>, ParseError<'p>> {
+    if matches!(kind, EntityKind::Unknown { .. }) {
+        return Ok(Vec::new());
+    }
+    let n: u32 = parser.parse()?;
+    parser.parse_n(n as usize)
+}
+
+/// Mirrors [`EntityKind`]'s variants without their data, e.g. for logging an
+/// entity's kind without borrowing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityKindVariants {
+    Shape,
+    Body,
+    Screen,
+    Water,
+    Vehicle,
+    Trigger,
+    Location,
+    Wheel,
+    Joint,
+    Script,
+    Light,
+    Unknown,
+}
+
+impl From<&EntityKind<'_>> for EntityKindVariants {
+    fn from(kind: &EntityKind<'_>) -> Self {
+        match kind {
+            EntityKind::Shape(_) => Self::Shape,
+            EntityKind::Body(_) => Self::Body,
+            EntityKind::Screen(_) => Self::Screen,
+            EntityKind::Water(_) => Self::Water,
+            EntityKind::Vehicle(_) => Self::Vehicle,
+            EntityKind::Trigger(_) => Self::Trigger,
+            EntityKind::Location(_) => Self::Location,
+            EntityKind::Wheel(_) => Self::Wheel,
+            EntityKind::Joint(_) => Self::Joint,
+            EntityKind::Script(_) => Self::Script,
+            EntityKind::Light(_) => Self::Light,
+            EntityKind::Unknown { .. } => Self::Unknown,
         }
     }
 }
@@ -282,7 +516,7 @@ impl<'a> Entity<'a> {
     }
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone)]
 pub enum EntityKind<'a> {
     Shape(Shape<'a>),
     Body(Body),
@@ -295,6 +529,125 @@ pub enum EntityKind<'a> {
     Joint(Joint),
     Script(Script<'a>),
     Light(Light<'a>),
+    /// A `kind_byte` this build doesn't recognize. Only produced when
+    /// [`Parser::lenient`] is set; in strict mode an unrecognized byte is a
+    /// parse error instead, as it always was. `raw` is every byte from
+    /// right after `kind_byte` up to (but not including) this entity's
+    /// trailing `0xefbeefbe` sentinel, which includes any children the
+    /// entity may have had: without knowing the kind's layout there's no
+    /// way to tell where its body ends and a length-prefixed `children`
+    /// array would begin, so they're swallowed as opaque bytes along with
+    /// it. The parser resyncs at the sentinel so the entity's siblings
+    /// still parse normally.
+    Unknown { kind_byte: u8, raw: &'a [u8] },
+}
+
+impl<'p> EntityKind<'p> {
+    fn parse<'a>(parser: &'a mut Parser<'p>, kind_byte: u8) -> Result<Self, ParseError<'p>>
+    where 'p: 'a {
+        Ok(match kind_byte {
+            2 => EntityKind::Shape(parser.parse()?),
+            1 => EntityKind::Body(parser.parse()?),
+            10 => EntityKind::Screen(parser.parse()?),
+            5 => EntityKind::Water(parser.parse()?),
+            8 => EntityKind::Vehicle(parser.parse()?),
+            11 => EntityKind::Trigger(parser.parse()?),
+            4 => EntityKind::Location(parser.parse()?),
+            9 => EntityKind::Wheel(parser.parse()?),
+            7 => EntityKind::Joint(parser.parse()?),
+            12 => EntityKind::Script(parser.parse()?),
+            3 => EntityKind::Light(parser.parse()?),
+            other if parser.lenient => EntityKind::Unknown {
+                kind_byte: other,
+                raw: resync_to_beef_beef(parser, other)?,
+            },
+            other => return Err(Parser::error(ParseErrorKind::NoReprIntMatch(u64::from(other)))),
+        })
+    }
+}
+
+impl Write for EntityKind<'_> {
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            EntityKind::Shape(shape) => shape.write(out),
+            EntityKind::Body(body) => body.write(out),
+            EntityKind::Screen(screen) => screen.write(out),
+            EntityKind::Water(water) => water.write(out),
+            EntityKind::Vehicle(vehicle) => vehicle.write(out),
+            EntityKind::Trigger(trigger) => trigger.write(out),
+            EntityKind::Location(location) => location.write(out),
+            EntityKind::Wheel(wheel) => wheel.write(out),
+            EntityKind::Joint(joint) => joint.write(out),
+            EntityKind::Script(script) => script.write(out),
+            EntityKind::Light(light) => light.write(out),
+            // `raw` already holds every byte from right after `kind_byte` up
+            // to (not including) the trailing sentinel, so writing it back
+            // verbatim reproduces the original bytes exactly.
+            EntityKind::Unknown { raw, .. } => out.extend_from_slice(raw),
+        }
+    }
+}
+
+/// Tries reading a `count: u32` at the parser's current position, then
+/// `count` child [`Entity`] values after it, succeeding only if that lands
+/// exactly on this entity's trailing `0xefbeefbe` marker with nothing left
+/// over. This is the same `count`-then-children shape [`entity_children`]
+/// reads for every *recognized* kind, so a position where it holds is a
+/// plausible boundary between an unrecognized kind's body and its children.
+/// Leaves the parser at an unspecified position on failure; the caller
+/// doesn't rely on it and resets `parser.i` itself before trying elsewhere.
+fn try_children_then_marker(parser: &mut Parser<'_>) -> bool {
+    const MARKER: [u8; 4] = [0xef, 0xbe, 0xef, 0xbe];
+    let Ok(count) = parser.parse::<u32>() else {
+        return false;
+    };
+    for _ in 0..count {
+        if parser.parse::<Entity<'_>>().is_err() {
+            return false;
+        }
+    }
+    parser.remaining().starts_with(&MARKER)
+}
+
+/// Scans ahead from the current position for this entity's *own* trailing
+/// `0xefbeefbe` sentinel and returns everything up to it, leaving the
+/// parser positioned right at the marker so [`Entity`]'s own `beef_beef`
+/// field still consumes and validates it.
+///
+/// Every entity, known kind or not, ends in one of these markers, so the
+/// first one found in the remaining bytes isn't necessarily this entity's:
+/// if it actually has children, their own markers come first, and nothing
+/// about a marker byte pattern distinguishes "this is mine" from "this
+/// belongs to my last child". What's unambiguous is the generic shape every
+/// entity's tail has (see [`entity_children`]): `count: u32`, then `count`
+/// children, then the marker, with nothing in between. So instead of
+/// guessing from markers, try every possible length for the unrecognized
+/// body, looking for the one split where the *rest* parses as exactly that
+/// shape. Falls back to the first marker found if no split works (e.g. a
+/// childless entity whose body doesn't happen to end in a literal zero
+/// count), matching the previous behavior for that case.
+fn resync_to_beef_beef<'p>(
+    parser: &mut Parser<'p>,
+    kind_byte: u8,
+) -> Result<&'p [u8], ParseError<'p>> {
+    const MARKER: [u8; 4] = [0xef, 0xbe, 0xef, 0xbe];
+    let start = parser.i;
+    let remaining_len = parser.remaining().len();
+    for body_len in 0..=remaining_len {
+        parser.i = start + body_len;
+        if try_children_then_marker(parser) {
+            let marker_i = parser.i;
+            parser.i = start;
+            return parser.take_dynamically(marker_i - start);
+        }
+    }
+    parser.i = start;
+    let offset = parser
+        .remaining()
+        .windows(MARKER.len())
+        .position(|window| window == MARKER)
+        .ok_or_else(|| Parser::error(ParseErrorKind::NoReprIntMatch(u64::from(kind_byte))))?;
+    parser.take_dynamically(offset)
 }
 
 impl<'a> EntityKind<'a> {
@@ -310,7 +663,10 @@ impl<'a> EntityKind<'a> {
             EntityKind::Location(location) => &location.transform,
             EntityKind::Light(light) => &light.transform,
             /* EntityKind::Failed(_) | */
-            EntityKind::Joint(_) | EntityKind::Wheel(_) | EntityKind::Script(_) => return None,
+            EntityKind::Joint(_)
+            | EntityKind::Wheel(_)
+            | EntityKind::Script(_)
+            | EntityKind::Unknown { .. } => return None,
         })
     }
 
@@ -328,18 +684,19 @@ impl<'a> EntityKind<'a> {
             EntityKind::Light(light) => &light.z_u8_start,
             EntityKind::Wheel(wheel) => &wheel.z_u8_start,
             EntityKind::Script(script) => &script.z_u8_start,
+            EntityKind::Unknown { .. } => &0,
         }
     }
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Exhaust {
     pub transform: Transform,
     // Values used in built-in levels: 0, 1.5, 2, 3
     pub z_f32: f32,
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Vehicle<'a> {
     pub z_u8_start: u8,
     pub body_handle: u32,
@@ -370,7 +727,7 @@ pub struct Vehicle<'a> {
     pub arm_rot: Option<f32>,
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct VehicleProperties<'a> {
     /// In m/s
     pub max_speed: f32,
@@ -401,7 +758,7 @@ fn guess_arm_rot<'p>(parser: &mut Parser<'p>) -> Result<Option<f32>, ParseError<
     })
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Vital {
     pub body_handle: u32,
     pub z_f32: f32,
@@ -409,13 +766,13 @@ pub struct Vital {
     pub shape_index: u32,
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct VehicleSound<'a> {
     pub name: &'a str,
     pub pitch: f32,
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Water {
     pub z_u8_start: u8,
     pub transform: Transform,
@@ -438,7 +795,7 @@ pub enum TintKind {
     Yellow,
 }
 
-#[derive(Clone, Parse)]
+#[derive(Clone, Parse, Write)]
 pub struct Palette<'a> {
     pub materials: [Material; PALETTE_SIZE],
     pub tint_tables: &'a [u8; N_TINTS * PALETTE_SIZE * TINT_SHADES],
@@ -482,7 +839,7 @@ impl<'a> ::core::fmt::Display for Palette<'a> {
     }
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Script<'a> {
     pub z_u8_start: u8,
     pub path: &'a str,
@@ -497,7 +854,7 @@ pub struct Script<'a> {
     pub sounds: Vec<ScriptSound<'a>>,
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Sound<'a> {
     pub path: &'a str,
     pub volume: f32,
@@ -506,7 +863,7 @@ pub struct Sound<'a> {
 pub mod environment {
     use super::*;
 
-    #[derive(Debug, Clone, Parse)]
+    #[derive(Debug, Clone, Parse, Write)]
     pub struct Environment<'a> {
         pub skybox: Skybox<'a>,
         pub exposure: Exposure,
@@ -518,7 +875,7 @@ pub mod environment {
         pub lights_fog_scale: f32,
     }
 
-    #[derive(Debug, Clone, Parse)]
+    #[derive(Debug, Clone, Parse, Write)]
     pub struct Skybox<'a> {
         pub texture: &'a str,
         pub color_intensity: Rgba,
@@ -531,7 +888,7 @@ pub mod environment {
         pub ambient_exposure: f32,
     }
 
-    #[derive(Debug, Clone, Parse)]
+    #[derive(Debug, Clone, Parse, Write)]
     pub struct Sun {
         pub tint_brightness: [f32; 3],
         pub tint: Rgba,
@@ -543,7 +900,7 @@ pub mod environment {
         pub glare: f32,
     }
 
-    #[derive(Debug, Clone, Parse)]
+    #[derive(Debug, Clone, Parse, Write)]
     pub struct Fog {
         pub color: Rgba,
         pub start: f32,
@@ -552,7 +909,7 @@ pub mod environment {
         pub exponent: f32,
     }
 
-    #[derive(Debug, Clone, Parse)]
+    #[derive(Debug, Clone, Parse, Write)]
     pub struct Water {
         pub wetness: f32,
         pub puddle_coverage: f32,
@@ -562,7 +919,7 @@ pub mod environment {
 }
 pub use environment::Environment;
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Trigger<'a> {
     pub z_u8_start: u8,
     pub transform: Transform,
@@ -575,7 +932,7 @@ pub struct Trigger<'a> {
     pub sound: TriggerSound<'a>,
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct TriggerSound<'a> {
     pub path: &'a str,
     pub ramp: f32,
@@ -583,15 +940,57 @@ pub struct TriggerSound<'a> {
     pub volume: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Parse)]
-#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TriggerGeometryKind {
-    Sphere = 1,
-    Box = 2,
-    Polygon = 3,
+    Sphere,
+    Box,
+    Polygon,
+    /// See [`ReprError`].
+    Unknown(u32),
+}
+
+impl TriggerGeometryKind {
+    fn from_repr(raw: u32) -> Result<Self, ReprError> {
+        Ok(match raw {
+            1 => Self::Sphere,
+            2 => Self::Box,
+            3 => Self::Polygon,
+            other => {
+                return Err(ReprError {
+                    enum_name: "TriggerGeometryKind",
+                    raw: other,
+                })
+            }
+        })
+    }
 }
 
-#[derive(Debug, Clone, Parse)]
+impl<'p> Parse<'p> for TriggerGeometryKind {
+    fn parse<'a>(parser: &'a mut Parser<'p>) -> Result<Self, ParseError<'p>>
+    where 'p: 'a {
+        let raw: u32 = parser.parse()?;
+        Ok(Self::from_repr(raw).unwrap_or_else(|err| Self::Unknown(err.raw)))
+    }
+}
+
+impl TriggerGeometryKind {
+    fn to_repr(&self) -> u32 {
+        match self {
+            Self::Sphere => 1,
+            Self::Box => 2,
+            Self::Polygon => 3,
+            Self::Unknown(raw) => *raw,
+        }
+    }
+}
+
+impl Write for TriggerGeometryKind {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.to_repr().write(out);
+    }
+}
+
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Body {
     pub z_u8_start: u8,
     pub transform: Transform,
@@ -602,20 +1001,20 @@ pub struct Body {
     pub z_u8: u8,
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Wheel<'a> {
     pub z_u8_start: u8,
     pub z_u8_108: &'a [u8; 108],
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Exposure {
     pub min: f32,
     pub max: f32,
     pub brightness_goal: f32,
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct BoundaryVertex {
     pub x: f32,
     pub z: f32,
@@ -638,13 +1037,27 @@ impl<'p> Parse<'p> for Registry<'p> {
     }
 }
 
-#[derive(Debug, Clone, Parse)]
+impl Write for Registry<'_> {
+    fn write(&self, out: &mut Vec<u8>) {
+        // HashMap doesn't preserve the original key order, so round-tripping
+        // a Registry may reorder its entries even though their contents are
+        // unchanged.
+        #[allow(clippy::cast_possible_truncation)]
+        (self.0.len() as u32).write(out);
+        for (key, value) in &self.0 {
+            key.write(out);
+            value.write(out);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Location {
     pub z_u8_start: u8,
     pub transform: Transform,
 }
 
-#[derive(Clone, Parse)]
+#[derive(Clone, Parse, Write)]
 pub struct Rgba(pub [f32; 4]);
 
 impl Rgba {
@@ -677,7 +1090,7 @@ impl Default for Rgba {
     }
 }
 
-#[derive(Clone, Parse)]
+#[derive(Clone, Parse, Write)]
 pub struct Rgb(pub [f32; 3]);
 
 impl Rgb {
@@ -698,7 +1111,7 @@ impl fmt::Debug for Rgb {
     }
 }
 
-#[derive(Debug, Clone, Parse)]
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Player {
     pub z_i32_3: [i32; 3],
     pub z_f32: [f32; 7],
@@ -710,7 +1123,7 @@ pub struct Player {
     pub z_f32_2: [f32; 2],
 }
 
-#[derive(Clone, PartialEq, Parse)]
+#[derive(Clone, PartialEq, Parse, Write)]
 pub enum LuaValue<'a> {
     Boolean(bool),
     Number(f64),
@@ -776,6 +1189,29 @@ impl<'p> Parse<'p> for LuaValue<'p> {
     }
 }
 
+impl Write for LuaValue<'_> {
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            LuaValue::Boolean(value) => {
+                1u32.write(out);
+                value.write(out);
+            }
+            LuaValue::Number(value) => {
+                3u32.write(out);
+                value.write(out);
+            }
+            LuaValue::String(value) => {
+                4u32.write(out);
+                value.write(out);
+            }
+            LuaValue::Table(value) => {
+                5u32.write(out);
+                value.write(out);
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct LuaTable<'a>(HashMap<LuaValue<'a>, LuaValue<'a>>);
 
@@ -803,21 +1239,74 @@ impl<'p> Parse<'p> for LuaTable<'p> {
     }
 }
 
-#[derive(Debug, Clone, Parse)]
+impl Write for LuaTable<'_> {
+    fn write(&self, out: &mut Vec<u8>) {
+        // Same HashMap-order caveat as Registry::write.
+        for (key, value) in &self.0 {
+            key.write(out);
+            value.write(out);
+        }
+        0u32.write(out);
+    }
+}
+
+#[derive(Debug, Clone, Parse, Write)]
 pub struct ScriptSound<'a> {
     pub kind: ScriptSoundKind,
     pub name: &'a str,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Parse)]
-#[repr(u32)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ScriptSoundKind {
-    Normal = 1,
-    Loop = 2,
-    Unknown3 = 3,
+    Normal,
+    Loop,
+    Unknown3,
+    /// See [`ReprError`].
+    Unknown(u32),
+}
+
+impl ScriptSoundKind {
+    fn from_repr(raw: u32) -> Result<Self, ReprError> {
+        Ok(match raw {
+            1 => Self::Normal,
+            2 => Self::Loop,
+            3 => Self::Unknown3,
+            other => {
+                return Err(ReprError {
+                    enum_name: "ScriptSoundKind",
+                    raw: other,
+                })
+            }
+        })
+    }
 }
 
-#[derive(Debug, Clone, Parse)]
+impl<'p> Parse<'p> for ScriptSoundKind {
+    fn parse<'a>(parser: &'a mut Parser<'p>) -> Result<Self, ParseError<'p>>
+    where 'p: 'a {
+        let raw: u32 = parser.parse()?;
+        Ok(Self::from_repr(raw).unwrap_or_else(|err| Self::Unknown(err.raw)))
+    }
+}
+
+impl ScriptSoundKind {
+    fn to_repr(&self) -> u32 {
+        match self {
+            Self::Normal => 1,
+            Self::Loop => 2,
+            Self::Unknown3 => 3,
+            Self::Unknown(raw) => *raw,
+        }
+    }
+}
+
+impl Write for ScriptSoundKind {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.to_repr().write(out);
+    }
+}
+
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Screen<'a> {
     pub z_u8_start: u8,
     pub transform: Transform,
@@ -851,7 +1340,19 @@ impl<'p> Parse<'p> for Tags<'p> {
     }
 }
 
-#[derive(Debug, Clone, Parse, PartialEq)]
+impl Write for Tags<'_> {
+    fn write(&self, out: &mut Vec<u8>) {
+        // Same caveat as Registry::write: HashMap order isn't original order.
+        #[allow(clippy::cast_possible_truncation)]
+        (self.0.len() as u8).write(out);
+        for (key, value) in &self.0 {
+            key.write(out);
+            value.write(out);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Parse, Write, PartialEq)]
 pub struct Transform {
     /// x, y, z
     pub pos: [f32; 3],
@@ -967,7 +1468,74 @@ impl RelativeEq for Transform {
     }
 }
 
-#[derive(Debug, Clone, Parse)]
+impl Transform {
+    /// Interpolates between `self` and `other` at `t` (0 = `self`, 1 =
+    /// `other`): linearly for `pos`, and via normalized spherical
+    /// interpolation (slerp) for `rot`, taking the shortest arc.
+    #[must_use]
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        let pos = [0, 1, 2].map(|i| self.pos[i] + (other.pos[i] - self.pos[i]) * t);
+
+        // Flip to the shortest arc if the quaternions point into opposite
+        // hemispheres.
+        let dot: f32 = self.rot.iter().zip(other.rot.iter()).map(|(a, b)| a * b).sum();
+        let (dot, other_rot) = if dot < 0. {
+            (-dot, other.rot.map(|component| -component))
+        } else {
+            (dot, other.rot)
+        };
+
+        let epsilon = f32::default_epsilon() * TOLERANCE_ADJUSTMENT;
+        let rot = if dot > 1. - epsilon {
+            // Nearly parallel: slerp's sin(theta) denominator is ~0, so fall
+            // back to a normalized lerp to avoid dividing by it.
+            let lerped = [0, 1, 2, 3].map(|i| self.rot[i] + (other_rot[i] - self.rot[i]) * t);
+            let len = lerped.iter().map(|component| component * component).sum::<f32>().sqrt();
+            lerped.map(|component| component / len)
+        } else {
+            let theta = dot.acos();
+            let sin_theta = theta.sin();
+            let self_weight = ((1. - t) * theta).sin() / sin_theta;
+            let other_weight = (t * theta).sin() / sin_theta;
+            [0, 1, 2, 3].map(|i| self.rot[i] * self_weight + other_rot[i] * other_weight)
+        };
+
+        Transform { pos, rot }
+    }
+}
+
+/// Samples interpolated [`Transform`]s from a sequence of timestamped
+/// keyframes, e.g. recorded shape poses, so callers can drive motion from
+/// timestamps rather than discrete keyframes.
+pub struct TransformTrack {
+    pub times: Vec<f32>,
+    pub transforms: Vec<Transform>,
+}
+
+impl TransformTrack {
+    /// Interpolates the transform at `time`, clamping to the first/last
+    /// keyframe if `time` falls outside the track's range.
+    ///
+    /// # Panics
+    /// Panics if `times` is empty, or `times` and `transforms` differ in
+    /// length.
+    #[must_use]
+    pub fn sample(&self, time: f32) -> Transform {
+        assert_eq!(self.times.len(), self.transforms.len());
+        assert!(!self.times.is_empty());
+        match self.times.partition_point(|&keyframe_time| keyframe_time <= time) {
+            0 => self.transforms[0].clone(),
+            i if i >= self.times.len() => self.transforms[self.times.len() - 1].clone(),
+            i => {
+                let (t0, t1) = (self.times[i - 1], self.times[i]);
+                let t = if t1 > t0 { (time - t0) / (t1 - t0) } else { 0. };
+                self.transforms[i - 1].lerp(&self.transforms[i], t)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Parse, Write)]
 pub struct Shape<'a> {
     pub z_u8_start: u8,
     pub transform: Transform,
@@ -1023,6 +1591,17 @@ impl<'p> Parse<'p> for Voxels<'p> {
     }
 }
 
+impl Write for Voxels<'_> {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.size.write(out);
+        if self.size[0] * self.size[1] * self.size[2] != 0 {
+            #[allow(clippy::cast_possible_truncation)]
+            (self.palette_index_runs.len() as u32).write(out);
+            out.extend_from_slice(&self.palette_index_runs);
+        }
+    }
+}
+
 pub struct BoxIter<I>
 where I: PrimInt
 {
@@ -1079,6 +1658,46 @@ impl<'a> Voxels<'a> {
     pub fn iter(&'a self) -> VoxelIter<'a> {
         VoxelIter::new(self)
     }
+
+    /// Builds a [`Voxels`] from a dense grid of palette indices, given in
+    /// the same `[0, 1, 2]` scan order [`BoxIter`]/[`VoxelIter`] use
+    /// (dimension 0 varies fastest). Compresses consecutive runs of the
+    /// same palette index into `(run_len - 1, palette)` pairs, splitting any
+    /// run longer than 256 voxels into multiple pairs, since `run_len - 1`
+    /// has to fit in a `u8`.
+    #[must_use]
+    pub fn from_grid(size: [u32; 3], indices: impl IntoIterator<Item = u8>) -> Self {
+        let mut runs = Vec::new();
+        let mut indices = indices.into_iter();
+        if let Some(mut current) = indices.next() {
+            let mut run_len: u32 = 1;
+            for index in indices {
+                if index == current && run_len < 256 {
+                    run_len += 1;
+                } else {
+                    #[allow(clippy::cast_possible_truncation)]
+                    runs.extend_from_slice(&[(run_len - 1) as u8, current]);
+                    current = index;
+                    run_len = 1;
+                }
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            runs.extend_from_slice(&[(run_len - 1) as u8, current]);
+        }
+        Self {
+            size,
+            palette_index_runs: Cow::Owned(runs),
+        }
+    }
+
+    /// Serializes back to the `.tdbin` byte layout this was parsed from
+    /// (or that [`Voxels::from_grid`] would parse back to an equal value).
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write(&mut out);
+        out
+    }
 }
 
 #[allow(clippy::type_complexity)]
@@ -1139,3 +1758,61 @@ impl fmt::Debug for Voxels<'_> {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn childless_entity(kind_byte: u8, kind: EntityKind<'_>) -> Entity<'_> {
+        Entity {
+            kind_byte,
+            handle: 1,
+            tags: Tags(HashMap::new()),
+            desc: "",
+            kind,
+            children: Vec::new(),
+            beef_beef: [0xef, 0xbe, 0xef, 0xbe],
+        }
+    }
+
+    #[test]
+    fn unknown_kind_with_a_real_child_resyncs_past_the_childs_own_marker() {
+        let child = childless_entity(
+            4, // Location
+            EntityKind::Location(Location { z_u8_start: 0, transform: Transform::default() }),
+        );
+        let mut child_bytes = Vec::new();
+        child.write(&mut child_bytes);
+
+        // An unrecognized kind's own (opaque-to-us) body, followed by a
+        // children count of 1 and a real child entity (ending in the child's
+        // own beef_beef marker).
+        let mut body = vec![0xAAu8; 6];
+        1u32.write(&mut body);
+        body.extend_from_slice(&child_bytes);
+
+        let mut bytes = Vec::new();
+        200u8.write(&mut bytes); // kind_byte this build doesn't recognize
+        1u32.write(&mut bytes);
+        Tags(HashMap::new()).write(&mut bytes);
+        "".write(&mut bytes);
+        bytes.extend_from_slice(&body);
+        bytes.extend_from_slice(&[0xef, 0xbe, 0xef, 0xbe]); // this entity's own marker
+
+        let mut parser = Parser::new(&bytes);
+        parser.lenient = true;
+        let parsed = Entity::parse(&mut parser).expect("lenient parse should resync past the child's own marker");
+
+        match &parsed.kind {
+            EntityKind::Unknown { raw, .. } => assert_eq!(*raw, body.as_slice()),
+            other => panic!("expected an Unknown kind, got {other:?}"),
+        }
+        assert!(parsed.children.is_empty());
+
+        let mut rewritten = Vec::new();
+        parsed.write(&mut rewritten);
+        assert_eq!(rewritten, bytes);
+    }
+}
@@ -0,0 +1,87 @@
+//! A queryable index over a [`Scene`]'s entity tree, resolving the opaque
+//! `u32` handles entities refer each other by (`Vehicle::body_handle`,
+//! `Joint::shape_handles`, `Vehicle::wheel_handles`, `Script::entity_handles`,
+//! ...) into the [`Entity`] they name, without a manual tree walk.
+
+use std::collections::HashMap;
+
+use crate::{Entity, Joint, Scene, Script, Vehicle};
+
+/// Maps entity handles to the entities and parents they resolve to. Built
+/// once from a [`Scene`] and then cheap to query repeatedly.
+pub struct SceneIndex<'a> {
+    by_handle: HashMap<u32, &'a Entity<'a>>,
+    parent_of: HashMap<u32, u32>,
+}
+
+impl<'a> SceneIndex<'a> {
+    #[must_use]
+    pub fn new(scene: &'a Scene<'a>) -> Self {
+        let mut index = Self {
+            by_handle: HashMap::new(),
+            parent_of: HashMap::new(),
+        };
+        for entity in &scene.entities {
+            index.insert(entity, None);
+        }
+        index
+    }
+
+    fn insert(&mut self, entity: &'a Entity<'a>, parent_handle: Option<u32>) {
+        self.by_handle.insert(entity.handle, entity);
+        if let Some(parent_handle) = parent_handle {
+            self.parent_of.insert(entity.handle, parent_handle);
+        }
+        for child in &entity.children {
+            self.insert(child, Some(entity.handle));
+        }
+    }
+
+    /// Resolves a handle to its entity. `None` if `handle` is dangling, i.e.
+    /// doesn't belong to any `Entity` in the scene.
+    #[must_use]
+    pub fn get(&self, handle: u32) -> Option<&'a Entity<'a>> {
+        self.by_handle.get(&handle).copied()
+    }
+
+    /// The entity `handle`'s entity is a direct child of, if any.
+    #[must_use]
+    pub fn parent_of(&self, handle: u32) -> Option<&'a Entity<'a>> {
+        self.get(*self.parent_of.get(&handle)?)
+    }
+
+    #[must_use]
+    pub fn body_of(&self, vehicle: &Vehicle) -> Option<&'a Entity<'a>> {
+        self.get(vehicle.body_handle)
+    }
+
+    #[must_use]
+    pub fn wheels_of(&self, vehicle: &Vehicle) -> Vec<&'a Entity<'a>> {
+        vehicle
+            .wheel_handles
+            .iter()
+            .filter_map(|&handle| self.get(handle))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn shapes_of(&self, joint: &Joint) -> [Option<&'a Entity<'a>>; 2] {
+        joint.shape_handles.map(|handle| self.get(handle))
+    }
+
+    #[must_use]
+    pub fn entities_of(&self, script: &Script) -> Vec<&'a Entity<'a>> {
+        script
+            .entity_handles
+            .iter()
+            .filter_map(|&handle| self.get(handle))
+            .collect()
+    }
+}
+
+impl<'a> Scene<'a> {
+    #[must_use]
+    pub fn index(&'a self) -> SceneIndex<'a> {
+        SceneIndex::new(self)
+    }
+}
@@ -11,20 +11,94 @@ use std::{
 };
 
 use anyhow::Result;
-use flate2::read::ZlibDecoder;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder};
 use owning_ref::OwningHandle;
 use structr::{get_end_path, write_debug_json, Parse, ParseError, Parser};
 use thiserror::Error;
 
 mod format;
+mod index;
+mod magicavoxel;
 #[cfg(feature = "mesh")]
 mod mesh;
+#[cfg(feature = "rayon")]
+mod par_voxel;
+mod resolve;
+mod validate;
 pub use format::*;
+pub use index::*;
+#[cfg(feature = "mesh")]
+pub use mesh::Mesh;
+#[cfg(feature = "rayon")]
+pub use par_voxel::*;
+pub use resolve::*;
+pub use validate::*;
+
+/// Which codec [`decompress_if_needed`] detected from a file's leading bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Zlib,
+    #[cfg(feature = "zstd")]
+    Zstd,
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
 
 #[derive(Debug, Error)]
-enum Error {
-    #[error(".vox error")]
+pub enum Error {
+    #[error("I/O error: {0}")]
     IO(#[from] io::Error),
+    #[error("decompressing as {codec:?}: {source}")]
+    Decompress { codec: Codec, source: io::Error },
+    #[error("decompressed output exceeded the {limit}-byte limit")]
+    OutputTooLarge { limit: usize },
+    #[error("allocation failed while decompressing: {0}")]
+    Alloc(#[from] std::collections::TryReserveError),
+}
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+const LZ4_MAGIC: [u8; 4] = [0x04, 0x22, 0x4D, 0x18];
+
+/// Caps how much memory [`decompress_if_needed`]/[`decompress_reader`] will
+/// commit to a single file's decompressed output, so a crafted compressed
+/// blob (a "zip bomb") can't expand to exhaust memory before the parser
+/// ever sees it.
+#[derive(Debug, Clone, Copy)]
+pub struct DecompressLimits {
+    pub max_output: usize,
+}
+
+impl Default for DecompressLimits {
+    /// 1 GiB: comfortably above any legitimate Teardown scene, small enough
+    /// to bound worst-case memory use from untrusted mod content.
+    fn default() -> Self {
+        Self { max_output: 1 << 30 }
+    }
+}
+
+/// Reads all of `reader` into a `Vec`, erroring out instead of growing past
+/// `limits.max_output` and surfacing allocation failure as
+/// [`Error::Alloc`] instead of aborting. IO errors reading `reader` are
+/// passed through `map_io` so callers can attach codec context.
+fn read_to_end_bounded<R: Read>(
+    mut reader: R,
+    limits: DecompressLimits,
+    map_io: impl Fn(io::Error) -> Error,
+) -> Result<Vec<u8>, Error> {
+    const CHUNK_LEN: usize = 64 * 1024;
+    let mut out = Vec::new();
+    let mut chunk = [0; CHUNK_LEN];
+    loop {
+        let read = reader.read(&mut chunk).map_err(&map_io)?;
+        if read == 0 {
+            return Ok(out);
+        }
+        if out.len() + read > limits.max_output {
+            return Err(Error::OutputTooLarge { limit: limits.max_output });
+        }
+        out.try_reserve(read)?;
+        out.extend_from_slice(&chunk[..read]);
+    }
 }
 
 fn read_bytes<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, io::Error> {
@@ -34,24 +108,129 @@ fn read_bytes<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, io::Error> {
     Ok(bytes)
 }
 
-fn decompress_if_needed(bytes: Vec<u8>) -> Result<Vec<u8>, io::Error> {
-    Ok(if bytes.starts_with(Scene::MAGIC) {
-        bytes
-    } else {
-        let mut new_bytes = Vec::with_capacity(bytes.len());
-        ZlibDecoder::new(Cursor::new(bytes)).read_to_end(&mut new_bytes)/*.map_err(|err| format!("Decompressing after magic mismatch: {:?}", err))*/?;
-        new_bytes
+/// Passes `bytes` through unchanged if it's already an uncompressed scene
+/// (starts with [`Scene::MAGIC`]), otherwise autodetects zstd/lz4 by their
+/// frame magic and falls back to zlib, which has no reliable magic of its
+/// own. The decompressed output is capped at `limits.max_output`.
+fn decompress_if_needed(bytes: Vec<u8>, limits: DecompressLimits) -> Result<Vec<u8>, Error> {
+    if bytes.starts_with(Scene::MAGIC) {
+        return Ok(bytes);
+    }
+    #[cfg(feature = "zstd")]
+    if bytes.starts_with(&ZSTD_MAGIC) {
+        let decoder = zstd::stream::read::Decoder::new(Cursor::new(bytes))
+            .map_err(|source| Error::Decompress { codec: Codec::Zstd, source })?;
+        return read_to_end_bounded(decoder, limits, |source| Error::Decompress { codec: Codec::Zstd, source });
+    }
+    #[cfg(feature = "lz4")]
+    if bytes.starts_with(&LZ4_MAGIC) {
+        let decoder = lz4::Decoder::new(Cursor::new(bytes))
+            .map_err(|source| Error::Decompress { codec: Codec::Lz4, source })?;
+        return read_to_end_bounded(decoder, limits, |source| Error::Decompress { codec: Codec::Lz4, source });
+    }
+    read_to_end_bounded(ZlibDecoder::new(Cursor::new(bytes)), limits, |source| Error::Decompress {
+        codec: Codec::Zlib,
+        source,
     })
 }
 
-pub fn read_to_uncompressed<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, io::Error> {
-    decompress_if_needed(read_bytes(path)?)
+pub fn read_to_uncompressed<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, Error> {
+    read_to_uncompressed_with_limits(path, DecompressLimits::default())
+}
+
+/// Like [`read_to_uncompressed`], but with an explicit decompression budget
+/// instead of [`DecompressLimits::default`], for callers parsing untrusted
+/// mod content.
+pub fn read_to_uncompressed_with_limits<P: AsRef<Path>>(
+    path: P,
+    limits: DecompressLimits,
+) -> Result<Vec<u8>, Error> {
+    decompress_if_needed(read_bytes(path)?, limits)
+}
+
+/// The longest magic prefix [`decompress_if_needed`]/[`decompress_reader`]
+/// sniff: [`Scene::MAGIC`] is 5 bytes, the zstd/lz4 frame magics are 4.
+const SNIFF_LEN: usize = 5;
+
+/// Reads up to `n` bytes of `reader` without losing them: returns what was
+/// read (fewer than `n` at EOF) alongside a reader that yields the rest of
+/// the stream as if nothing had been consumed.
+fn peek_prefix<R: Read>(mut reader: R, n: usize) -> Result<(Vec<u8>, R), io::Error> {
+    let mut prefix = vec![0; n];
+    let mut filled = 0;
+    while filled < n {
+        match reader.read(&mut prefix[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    prefix.truncate(filled);
+    Ok((prefix, reader))
+}
+
+/// Like [`decompress_if_needed`], but sniffs the codec off a peeked prefix
+/// and chains the matching decompressor onto `reader` as a streaming `Read`
+/// adapter, instead of decompressing an already fully-materialized buffer.
+/// This keeps parsing from a socket, archive member, or memory map from
+/// needing two full-size buffers (one for the compressed bytes, one for the
+/// decompressed ones) at once. The decompressed output is capped at
+/// `limits.max_output`.
+fn decompress_reader<R: Read>(reader: R, limits: DecompressLimits) -> Result<Vec<u8>, Error> {
+    let (prefix, reader) = peek_prefix(reader, SNIFF_LEN).map_err(Error::IO)?;
+    let chained = Cursor::new(prefix.clone()).chain(reader);
+    if prefix.starts_with(Scene::MAGIC) {
+        // Already uncompressed: re-prepend the peeked prefix and pass the rest through.
+        return read_to_end_bounded(chained, limits, Error::IO);
+    }
+    #[cfg(feature = "zstd")]
+    if prefix.starts_with(&ZSTD_MAGIC) {
+        let decoder = zstd::stream::read::Decoder::new(chained)
+            .map_err(|source| Error::Decompress { codec: Codec::Zstd, source })?;
+        return read_to_end_bounded(decoder, limits, |source| Error::Decompress { codec: Codec::Zstd, source });
+    }
+    #[cfg(feature = "lz4")]
+    if prefix.starts_with(&LZ4_MAGIC) {
+        let decoder = lz4::Decoder::new(chained).map_err(|source| Error::Decompress { codec: Codec::Lz4, source })?;
+        return read_to_end_bounded(decoder, limits, |source| Error::Decompress { codec: Codec::Lz4, source });
+    }
+    read_to_end_bounded(ZlibDecoder::new(chained), limits, |source| Error::Decompress {
+        codec: Codec::Zlib,
+        source,
+    })
+}
+
+/// Parses a scene straight from any [`Read`], decompressing (or not) as it
+/// streams, and only ever materializing the one owned, decompressed buffer
+/// [`OwnedScene`] needs to borrow from. See [`decompress_reader`].
+pub fn parse_reader<R: Read>(reader: R) -> Result<OwnedScene> {
+    parse_reader_with_limits(reader, DecompressLimits::default())
+}
+
+/// Like [`parse_reader`], but with an explicit decompression budget instead
+/// of [`DecompressLimits::default`], for callers parsing untrusted mod
+/// content.
+pub fn parse_reader_with_limits<R: Read>(reader: R, limits: DecompressLimits) -> Result<OwnedScene> {
+    let uncompressed = decompress_reader(reader, limits)?;
+    OwningHandle::try_new(uncompressed, |uncompressed_ref| {
+        // Safety: I have no idea.
+        unsafe { Ok(Box::new(parse_uncompressed(&*uncompressed_ref)?)) }
+    })
 }
 
 pub fn parse_uncompressed(bytes: &[u8]) -> Result<Scene<'_>, ParseError<'_>> {
     Scene::parse(&mut Parser::new(bytes))
 }
 
+/// Like [`parse_uncompressed`], but sets [`Parser::lenient`] first, so an
+/// entity kind this build doesn't recognize becomes `EntityKind::Unknown`
+/// instead of failing the whole parse. Use this for `.tdbin` files that
+/// might come from a newer game version or a mod.
+pub fn parse_uncompressed_lenient(bytes: &[u8]) -> Result<Scene<'_>, ParseError<'_>> {
+    let mut parser = Parser::new(bytes);
+    parser.lenient = true;
+    Scene::parse(&mut parser)
+}
+
 pub type OwnedScene = OwningHandle<Vec<u8>, Box<Scene<'static>>>;
 
 pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<OwnedScene> {
@@ -62,6 +241,57 @@ pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<OwnedScene> {
     })
 }
 
+/// Like [`parse_file`], but via [`parse_uncompressed_lenient`].
+pub fn parse_file_lenient<P: AsRef<Path>>(path: P) -> Result<OwnedScene> {
+    let uncompressed = read_to_uncompressed(path)?;
+    OwningHandle::try_new(uncompressed, |uncompressed_ref| {
+        // Safety: I have no idea.
+        unsafe { Ok(Box::new(parse_uncompressed_lenient(&*uncompressed_ref)?)) }
+    })
+}
+
+/// How [`write_scene`]/[`write_scene_file`] compress a [`Scene`]'s
+/// serialized bytes, mirroring the codecs [`decompress_if_needed`] can read
+/// back.
+#[derive(Debug, Clone, Copy)]
+pub enum Compression {
+    /// No compression: write [`Scene::to_bytes`] through unchanged.
+    Store,
+    /// Deflate via `flate2`, the same codec the game itself writes.
+    Zlib,
+    /// Deflate via `zopfli`: much slower than `flate2`, but produces
+    /// smaller zlib streams at its highest effort setting.
+    #[cfg(feature = "zopfli")]
+    Zopfli,
+}
+
+/// Serializes `scene` back to its `.tdbin` byte layout (already
+/// `Scene::MAGIC`-framed, via [`Scene::to_bytes`]) and writes it to
+/// `writer`, compressed as `compression` directs.
+pub fn write_scene<W: io::Write>(scene: &Scene, mut writer: W, compression: Compression) -> Result<(), Error> {
+    let raw = scene.to_bytes();
+    match compression {
+        Compression::Store => writer.write_all(&raw).map_err(Error::IO),
+        Compression::Zlib => {
+            let mut encoder = ZlibEncoder::new(writer, flate2::Compression::best());
+            encoder.write_all(&raw).map_err(Error::IO)?;
+            encoder.finish().map_err(Error::IO)?;
+            Ok(())
+        }
+        #[cfg(feature = "zopfli")]
+        Compression::Zopfli => {
+            zopfli::compress(&zopfli::Options::default(), &zopfli::Format::Zlib, raw.as_slice(), &mut writer)
+                .map_err(Error::IO)
+        }
+    }
+}
+
+/// Like [`write_scene`], but creating (or overwriting) a file at `path`
+/// instead of writing to an arbitrary [`std::io::Write`].
+pub fn write_scene_file<P: AsRef<Path>>(path: P, scene: &Scene, compression: Compression) -> Result<(), Error> {
+    write_scene(scene, File::create(path).map_err(Error::IO)?, compression)
+}
+
 pub fn test_file<P: AsRef<Path>>(path: P, debug: bool) -> Result<(), Box<dyn StdError>> {
     let uncompressed = read_to_uncompressed(path)?;
     let mut parser = Parser::new(&uncompressed);
@@ -0,0 +1,253 @@
+//! Exports [`Shape`] voxel data to the MagicaVoxel `.vox` interchange
+//! format, giving users a bridge into existing voxel editors and renderers.
+//! `.vox` caps each model at 256 voxels per axis, so shapes larger than
+//! that are split into multiple models placed via transform nodes.
+
+use std::collections::BTreeMap;
+
+use crate::{format::Shape, Palette};
+
+const MODEL_LIMIT: i32 = 256;
+
+struct Chunk {
+    id: &'static [u8; 4],
+    content: Vec<u8>,
+    children: Vec<u8>,
+}
+
+impl Chunk {
+    fn leaf(id: &'static [u8; 4], content: Vec<u8>) -> Self {
+        Self { id, content, children: Vec::new() }
+    }
+
+    fn parent(id: &'static [u8; 4], children: Vec<u8>) -> Self {
+        Self { id, content: Vec::new(), children }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.id);
+        #[allow(clippy::cast_possible_truncation)]
+        out.extend_from_slice(&(self.content.len() as u32).to_le_bytes());
+        #[allow(clippy::cast_possible_truncation)]
+        out.extend_from_slice(&(self.children.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.content);
+        out.extend_from_slice(&self.children);
+    }
+}
+
+fn write_vox_string(out: &mut Vec<u8>, s: &str) {
+    #[allow(clippy::cast_possible_truncation)]
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_vox_dict(out: &mut Vec<u8>, entries: &[(&str, String)]) {
+    #[allow(clippy::cast_possible_truncation)]
+    out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    for (key, value) in entries {
+        write_vox_string(out, key);
+        write_vox_string(out, value);
+    }
+}
+
+fn size_chunk(size: [i32; 3]) -> Chunk {
+    let mut content = Vec::with_capacity(12);
+    for dim in size {
+        #[allow(clippy::cast_sign_loss)]
+        content.extend_from_slice(&(dim as u32).to_le_bytes());
+    }
+    Chunk::leaf(b"SIZE", content)
+}
+
+fn xyzi_chunk(voxels: &[(u8, u8, u8, u8)]) -> Chunk {
+    let mut content = Vec::with_capacity(4 + voxels.len() * 4);
+    #[allow(clippy::cast_possible_truncation)]
+    content.extend_from_slice(&(voxels.len() as u32).to_le_bytes());
+    for &(x, y, z, color_index) in voxels {
+        content.extend_from_slice(&[x, y, z, color_index]);
+    }
+    Chunk::leaf(b"XYZI", content)
+}
+
+fn pack_chunk(num_models: usize) -> Chunk {
+    #[allow(clippy::cast_possible_truncation)]
+    Chunk::leaf(b"PACK", (num_models as u32).to_le_bytes().to_vec())
+}
+
+/// A transform node (`nTRN`) placing `child_id` at `offset`.
+fn transform_chunk(node_id: i32, child_id: i32, offset: [i32; 3]) -> Chunk {
+    let mut content = Vec::new();
+    content.extend_from_slice(&node_id.to_le_bytes());
+    write_vox_dict(&mut content, &[]);
+    content.extend_from_slice(&child_id.to_le_bytes());
+    content.extend_from_slice(&(-1i32).to_le_bytes()); // reserved id
+    content.extend_from_slice(&(-1i32).to_le_bytes()); // layer id: -1 = default
+    content.extend_from_slice(&1i32.to_le_bytes()); // num frames
+    let translation = format!("{} {} {}", offset[0], offset[1], offset[2]);
+    write_vox_dict(&mut content, &[("_t", translation)]);
+    Chunk::leaf(b"nTRN", content)
+}
+
+/// A group node (`nGRP`) holding `children`.
+fn group_chunk(node_id: i32, children: &[i32]) -> Chunk {
+    let mut content = Vec::new();
+    content.extend_from_slice(&node_id.to_le_bytes());
+    write_vox_dict(&mut content, &[]);
+    #[allow(clippy::cast_possible_truncation)]
+    content.extend_from_slice(&(children.len() as i32).to_le_bytes());
+    for &child in children {
+        content.extend_from_slice(&child.to_le_bytes());
+    }
+    Chunk::leaf(b"nGRP", content)
+}
+
+/// A shape node (`nSHP`) referencing model `model_id`.
+fn shape_chunk(node_id: i32, model_id: i32) -> Chunk {
+    let mut content = Vec::new();
+    content.extend_from_slice(&node_id.to_le_bytes());
+    write_vox_dict(&mut content, &[]);
+    content.extend_from_slice(&1i32.to_le_bytes()); // num models
+    content.extend_from_slice(&model_id.to_le_bytes());
+    write_vox_dict(&mut content, &[]); // model attributes
+    Chunk::leaf(b"nSHP", content)
+}
+
+/// A `.vox` color index `c` (1..=255) resolves to `rgba_chunk[c - 1]`, so
+/// `materials[0]` (Teardown's "no voxel" index) is dropped and everything
+/// else shifts down by one; the last slot is unused.
+fn palette_chunk(palette: Option<&Palette>) -> Chunk {
+    let mut content = Vec::with_capacity(256 * 4);
+    for color_index in 0..256usize {
+        let rgba = palette
+            .and_then(|palette| palette.materials.get(color_index + 1))
+            .map_or([0, 0, 0, 0], |material| material.rgba.u8());
+        content.extend_from_slice(&rgba);
+    }
+    Chunk::leaf(b"RGBA", content)
+}
+
+struct Model {
+    size: [i32; 3],
+    offset: [i32; 3],
+    voxels: Vec<(u8, u8, u8, u8)>,
+}
+
+impl<'a> Shape<'a> {
+    /// Splits this shape's voxels into `<= 256`-per-axis blocks, each
+    /// keeping its own local `0..256` voxel coordinates plus the world
+    /// offset it was cut from.
+    fn vox_models(&'a self) -> Vec<Model> {
+        #[allow(clippy::cast_possible_wrap)]
+        let size = self.voxels.size.map(|dim| dim as i32);
+
+        let mut by_block: BTreeMap<[i32; 3], Vec<(u8, u8, u8, u8)>> = BTreeMap::new();
+        for (coord, palette_index) in self.iter_voxels().filter(|&(_, index)| index != 0) {
+            let block = coord.map(|c| c.div_euclid(MODEL_LIMIT) * MODEL_LIMIT);
+            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+            let local = [0, 1, 2].map(|axis| (coord[axis] - block[axis]) as u8);
+            by_block
+                .entry(block)
+                .or_default()
+                .push((local[0], local[1], local[2], palette_index));
+        }
+
+        if by_block.is_empty() {
+            return vec![Model { size: [1, 1, 1], offset: [0, 0, 0], voxels: Vec::new() }];
+        }
+
+        by_block
+            .into_iter()
+            .map(|(offset, voxels)| {
+                let model_size = [0, 1, 2].map(|axis| i32::min(MODEL_LIMIT, size[axis] - offset[axis]));
+                Model { size: model_size, offset, voxels }
+            })
+            .collect()
+    }
+
+    /// Serializes this shape's voxels and the palette it references into a
+    /// standalone MagicaVoxel `.vox` file.
+    #[must_use]
+    pub fn to_magicavoxel(&self, palettes: &[Palette]) -> Vec<u8> {
+        let models = self.vox_models();
+
+        let mut children = Vec::new();
+        if models.len() > 1 {
+            pack_chunk(models.len()).write(&mut children);
+        }
+        for model in &models {
+            size_chunk(model.size).write(&mut children);
+            xyzi_chunk(&model.voxels).write(&mut children);
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let group_children: Vec<i32> = (0..models.len() as i32).map(|model_id| 2 + 2 * model_id).collect();
+        transform_chunk(0, 1, [0, 0, 0]).write(&mut children);
+        group_chunk(1, &group_children).write(&mut children);
+        for (model_id, model) in models.iter().enumerate() {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let model_id = model_id as i32;
+            transform_chunk(2 + 2 * model_id, 3 + 2 * model_id, model.offset).write(&mut children);
+            shape_chunk(3 + 2 * model_id, model_id).write(&mut children);
+        }
+
+        palette_chunk(palettes.get(self.palette as usize)).write(&mut children);
+
+        let main = Chunk::parent(b"MAIN", children);
+        let mut out = Vec::new();
+        out.extend_from_slice(b"VOX ");
+        out.extend_from_slice(&200u32.to_le_bytes());
+        main.write(&mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{Transform, Voxels};
+
+    use super::*;
+
+    fn sparse_shape<'a>(size: [u32; 3], indices: impl IntoIterator<Item = u8>) -> Shape<'a> {
+        Shape {
+            z_u8_start: 0,
+            transform: Transform::default(),
+            z_u8_4: [0; 4],
+            density: 1.,
+            strength: 1.,
+            texture_tile: 0,
+            starting_world_position: [0., 0., 0.],
+            texture_weight: 0.,
+            z_f32: 0.,
+            z1_u8: 0,
+            z2_u8: 0,
+            voxels: Voxels::from_grid(size, indices),
+            palette: 0,
+            voxel_scaling: 1.,
+            z_i32_3: [0, 0],
+            z3_u8: 0,
+        }
+    }
+
+    #[test]
+    fn vox_models_drops_air_voxels() {
+        // A 2x2x2 cube with only the two opposite corners filled.
+        let shape = sparse_shape([2, 2, 2], [5, 0, 0, 0, 0, 0, 0, 7]);
+        let models = shape.vox_models();
+        assert_eq!(models.len(), 1);
+        let voxels = &models[0].voxels;
+        assert_eq!(voxels.len(), 2);
+        assert!(voxels.contains(&(0, 0, 0, 5)));
+        assert!(voxels.contains(&(1, 1, 1, 7)));
+    }
+
+    #[test]
+    fn to_magicavoxel_xyzi_chunk_only_lists_nonzero_voxels() {
+        let shape = sparse_shape([2, 2, 2], [5, 0, 0, 0, 0, 0, 0, 7]);
+        let out = shape.to_magicavoxel(&[]);
+        // XYZI chunk: 4-byte id, 4-byte content len, 4-byte children len,
+        // then a 4-byte voxel count followed by 4 bytes per voxel.
+        let xyzi_offset = out.windows(4).position(|w| w == b"XYZI").unwrap();
+        let num_voxels = u32::from_le_bytes(out[xyzi_offset + 12..xyzi_offset + 16].try_into().unwrap());
+        assert_eq!(num_voxels, 2);
+    }
+}
@@ -1,11 +1,25 @@
 use std::convert::TryInto;
 use building_blocks::{
     core::prelude::*,
-    mesh::{greedy_quads, padded_greedy_quads_chunk_extent, GreedyQuadsBuffer, IsOpaque, MergeVoxel},
+    mesh::{
+        greedy_quads, padded_greedy_quads_chunk_extent, GreedyQuadsBuffer, IsOpaque, MergeVoxel,
+        RIGHT_HANDED_Y_UP_CONFIG,
+    },
     storage::prelude::*,
 };
 use crate::{Palette, PaletteIndex, format::Shape};
 
+/// Triangle geometry produced by [`Shape::greedy_mesh`], ready to upload to
+/// a GPU vertex/index buffer. `palette_indices` has one entry per quad
+/// (four vertices, six indices), not per vertex.
+#[derive(Debug, Default, Clone)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub palette_indices: Vec<u8>,
+    pub indices: Vec<u32>,
+}
+
 impl<'a> Shape<'a> {
     pub fn to_mesh(&self, palettes: &[Palette]) -> (Array3<PaletteIndex>, GreedyQuadsBuffer) {
         let size: [i32; 3] = self.voxels.size.map(|dim| dim.try_into().expect("shape size too large"));
@@ -26,6 +40,32 @@ impl<'a> Shape<'a> {
         greedy_quads(&array, &extent, &mut buffer);
         (array, buffer)
     }
+
+    /// Greedy-meshes this shape's voxels into triangle geometry, merging
+    /// adjacent same-palette voxels into maximal quads via [`Shape::to_mesh`]
+    /// and expanding those quads into positions, normals, a palette index
+    /// per quad, and a triangle index buffer, scaled by `voxel_scaling` and
+    /// offset by `starting_world_position`.
+    #[must_use]
+    pub fn greedy_mesh(&self, palettes: &[Palette]) -> Mesh {
+        let (array, buffer) = self.to_mesh(palettes);
+        let mut mesh = Mesh::default();
+        for (group, face) in buffer.quad_groups.iter().zip(RIGHT_HANDED_Y_UP_CONFIG.faces.iter()) {
+            for quad in &group.quads {
+                let start_index: u32 = mesh.positions.len().try_into().expect("mesh too large");
+                mesh.positions.extend(face.quad_mesh_positions(&quad.quad, self.voxel_scaling));
+                mesh.normals.extend(face.quad_mesh_normals());
+                mesh.indices.extend(face.quad_mesh_indices(start_index));
+                mesh.palette_indices.push(array.get(quad.minimum).voxel_merge_value());
+            }
+        }
+        for position in &mut mesh.positions {
+            for (axis, offset) in position.iter_mut().zip(self.starting_world_position.iter()) {
+                *axis += offset;
+            }
+        }
+        mesh
+    }
 }
 
 impl MergeVoxel for PaletteIndex {
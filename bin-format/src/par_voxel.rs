@@ -0,0 +1,199 @@
+//! Rayon-backed parallel counterpart to [`VoxelIter`], gated behind the
+//! `rayon` feature. `palette_index_runs` is run-length encoded, so splitting
+//! the voxel space for parallel work isn't a matter of just slicing a
+//! `Vec`: a prefix-sum table over the runs lets a linear voxel index be
+//! mapped back to the run (and offset within it) that covers it via binary
+//! search, so any contiguous `[lo, hi)` range of voxels can be located and
+//! produced independently.
+
+use std::sync::Arc;
+
+use rayon::iter::plumbing::{bridge, Consumer, Producer, ProducerCallback, UnindexedConsumer};
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+
+use crate::Voxels;
+
+/// `prefix[i]` is the number of voxels covered by runs `0..=i`, i.e. the
+/// linear index one past the last voxel of run `i`.
+fn build_prefix(runs: &[u8]) -> Arc<[u32]> {
+    let mut total = 0u32;
+    runs.array_chunks::<2>()
+        .map(|&[n_minus_1, _palette]| {
+            total += u32::from(n_minus_1) + 1;
+            total
+        })
+        .collect()
+}
+
+fn locate_run(prefix: &[u32], k: u32) -> usize {
+    prefix.partition_point(|&end| end <= k)
+}
+
+/// Maps a linear voxel index back to 3D coordinates, in the same order
+/// [`BoxIter`](crate::BoxIter) produces them: `order[0]` varies fastest.
+#[allow(clippy::cast_possible_wrap)]
+fn coords_from_linear(size: [i32; 3], order: [usize; 3], k: u32) -> [i32; 3] {
+    let mut coords = [0; 3];
+    let mut remaining = k as i32;
+    for &dim_i in &order {
+        let extent = size[dim_i];
+        coords[dim_i] = remaining % extent;
+        remaining /= extent;
+    }
+    coords
+}
+
+fn voxel_at(size: [i32; 3], order: [usize; 3], runs: &[u8], prefix: &[u32], k: u32) -> ([i32; 3], u8) {
+    let run = locate_run(prefix, k);
+    let palette = runs[run * 2 + 1];
+    (coords_from_linear(size, order, k), palette)
+}
+
+#[derive(Clone)]
+struct VoxelRange<'a> {
+    size: [i32; 3],
+    order: [usize; 3],
+    runs: &'a [u8],
+    prefix: Arc<[u32]>,
+    lo: u32,
+    hi: u32,
+}
+
+struct VoxelRangeIter<'a>(VoxelRange<'a>);
+
+impl<'a> Iterator for VoxelRangeIter<'a> {
+    type Item = ([i32; 3], u8);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.lo >= self.0.hi {
+            return None;
+        }
+        let item = voxel_at(self.0.size, self.0.order, self.0.runs, &self.0.prefix, self.0.lo);
+        self.0.lo += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.0.hi - self.0.lo) as usize;
+        (len, Some(len))
+    }
+}
+
+impl<'a> DoubleEndedIterator for VoxelRangeIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.0.lo >= self.0.hi {
+            return None;
+        }
+        self.0.hi -= 1;
+        Some(voxel_at(self.0.size, self.0.order, self.0.runs, &self.0.prefix, self.0.hi))
+    }
+}
+
+impl<'a> ExactSizeIterator for VoxelRangeIter<'a> {}
+
+impl<'a> Producer for VoxelRange<'a> {
+    type Item = ([i32; 3], u8);
+    type IntoIter = VoxelRangeIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        VoxelRangeIter(self)
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let mid = self.lo + index as u32;
+        let left = Self { hi: mid, ..self.clone() };
+        let right = Self { lo: mid, ..self };
+        (left, right)
+    }
+}
+
+/// The indexed, unfiltered counterpart to [`VoxelParIter`]: every voxel in
+/// the shape, including zero-palette ones. A `rayon::iter::Filter` over
+/// this (what [`VoxelParIter`] is) can't implement [`IndexedParallelIterator`]
+/// since filtering changes the count, so callers that need exact splitting
+/// (e.g. to `zip` against another indexed parallel iterator) should use
+/// [`Voxels::par_iter_raw`] and filter out zero-palette voxels themselves.
+pub struct RawVoxelParIter<'a>(VoxelRange<'a>);
+
+impl<'a> ParallelIterator for RawVoxelParIter<'a> {
+    type Item = ([i32; 3], u8);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where C: UnindexedConsumer<Self::Item> {
+        bridge(self, consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<'a> IndexedParallelIterator for RawVoxelParIter<'a> {
+    fn len(&self) -> usize {
+        (self.0.hi - self.0.lo) as usize
+    }
+
+    fn drive<C>(self, consumer: C) -> C::Result
+    where C: Consumer<Self::Item> {
+        bridge(self, consumer)
+    }
+
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where CB: ProducerCallback<Self::Item> {
+        callback.callback(self.0)
+    }
+}
+
+/// Rayon-parallel counterpart to [`VoxelIter`], yielding the same
+/// `([i32; 3], u8)` items (non-zero palette indices only). Get one via
+/// [`Voxels::par_iter`].
+pub struct VoxelParIter<'a>(
+    rayon::iter::Filter<RawVoxelParIter<'a>, fn(&([i32; 3], u8)) -> bool>,
+);
+
+fn is_non_zero_palette(item: &([i32; 3], u8)) -> bool {
+    item.1 != 0
+}
+
+impl<'a> ParallelIterator for VoxelParIter<'a> {
+    type Item = ([i32; 3], u8);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where C: UnindexedConsumer<Self::Item> {
+        self.0.drive_unindexed(consumer)
+    }
+}
+
+impl<'a> Voxels<'a> {
+    fn voxel_range(&'a self) -> VoxelRange<'a> {
+        let runs = self.palette_index_runs.as_ref();
+        let prefix = build_prefix(runs);
+        #[allow(clippy::cast_possible_wrap)]
+        let size = self.size.map(|dim| dim as i32);
+        let hi = self.size[0] * self.size[1] * self.size[2];
+        VoxelRange {
+            size,
+            order: [0, 1, 2],
+            runs,
+            prefix,
+            lo: 0,
+            hi,
+        }
+    }
+
+    #[must_use]
+    pub fn par_iter(&'a self) -> VoxelParIter<'a> {
+        VoxelParIter(
+            RawVoxelParIter(self.voxel_range())
+                .filter(is_non_zero_palette as fn(&([i32; 3], u8)) -> bool),
+        )
+    }
+
+    /// Like [`Voxels::par_iter`], but unfiltered (zero-palette voxels
+    /// included) and [`IndexedParallelIterator`] so callers needing exact
+    /// splitting (e.g. `zip`) have a path to it. See [`RawVoxelParIter`].
+    #[must_use]
+    pub fn par_iter_raw(&'a self) -> RawVoxelParIter<'a> {
+        RawVoxelParIter(self.voxel_range())
+    }
+}
@@ -0,0 +1,98 @@
+//! Opt-in resolving loader: [`parse_file`](crate::parse_file) stops at the
+//! top-level scene and leaves any externally-referenced asset (currently, a
+//! [`Script`](crate::Script)'s Lua file path) as a raw string. A caller that
+//! wants those assets fetched and, where they turn out to be another
+//! `.tdbin` scene, parsed too, can instead call [`parse_file_with_resolver`].
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use owning_ref::OwningHandle;
+
+use crate::{decompress_if_needed, parse_file, parse_uncompressed, DecompressLimits, EntityKind, Error, OwnedScene, Scene};
+
+/// Fetches the bytes of an asset referenced by `path` (already resolved
+/// relative to the scene file's directory). Implemented for any
+/// `FnMut(&Path) -> io::Result<Vec<u8>>`, e.g. a closure reading from disk,
+/// a mod archive, or an in-memory map built for tests.
+pub trait AssetResolver {
+    fn resolve(&mut self, path: &Path) -> io::Result<Vec<u8>>;
+}
+
+impl<F: FnMut(&Path) -> io::Result<Vec<u8>>> AssetResolver for F {
+    fn resolve(&mut self, path: &Path) -> io::Result<Vec<u8>> {
+        self(path)
+    }
+}
+
+/// One externally-referenced asset a resolver fetched: the bytes it
+/// returned (already run through [`decompress_if_needed`]), and, if those
+/// bytes turned out to start with [`Scene::MAGIC`], the scene parsed from
+/// them.
+pub struct ResolvedAsset {
+    pub bytes: Vec<u8>,
+    pub scene: Option<OwnedScene>,
+}
+
+/// Resolves every externally-referenced asset path in `scene` (today,
+/// [`Script`](crate::Script) paths) through `resolver`, relative to
+/// `scene_dir`. Only `scene`'s own references are resolved: assets
+/// referenced from a sub-scene that a resolved asset parses to are not
+/// fetched in turn. Already-seen paths are only fetched once, so a script
+/// shared by many entities costs one resolve, not one per reference. A resolver failure, or a failure to decompress/parse what it
+/// returned, is recorded against that path in `failures` rather than
+/// aborting the rest of the scene.
+#[must_use]
+pub fn resolve_assets(
+    scene: &Scene,
+    scene_dir: &Path,
+    mut resolver: impl AssetResolver,
+) -> (HashMap<String, ResolvedAsset>, Vec<(String, Error)>) {
+    let mut resolved = HashMap::new();
+    let mut failures = Vec::new();
+    for entity in scene.iter_entities() {
+        if let EntityKind::Script(script) = &entity.kind {
+            let reference = script.path.to_string();
+            if resolved.contains_key(&reference) || failures.iter().any(|(path, _)| *path == reference) {
+                continue;
+            }
+            match resolve_one(scene_dir, &reference, &mut resolver) {
+                Ok(asset) => {
+                    resolved.insert(reference, asset);
+                }
+                Err(err) => failures.push((reference, err)),
+            }
+        }
+    }
+    (resolved, failures)
+}
+
+fn resolve_one(scene_dir: &Path, reference: &str, resolver: &mut impl AssetResolver) -> Result<ResolvedAsset, Error> {
+    let bytes = resolver.resolve(&scene_dir.join(reference)).map_err(Error::IO)?;
+    let bytes = decompress_if_needed(bytes, DecompressLimits::default())?;
+    let scene = if bytes.starts_with(Scene::MAGIC) {
+        Some(OwningHandle::try_new(bytes.clone(), |uncompressed_ref| {
+            // Safety: see parse_file's identical cast; the handle keeps `bytes` alive
+            // for as long as the `Scene<'static>` borrowed from it is reachable.
+            unsafe { Ok(Box::new(parse_uncompressed(&*uncompressed_ref)?)) }
+        })?)
+    } else {
+        None
+    };
+    Ok(ResolvedAsset { bytes, scene })
+}
+
+/// Like [`parse_file`], but also resolves every asset [`resolve_assets`]
+/// finds, relative to `path`'s parent directory.
+pub fn parse_file_with_resolver<P: AsRef<Path>>(
+    path: P,
+    resolver: impl AssetResolver,
+) -> anyhow::Result<(OwnedScene, HashMap<String, ResolvedAsset>, Vec<(String, Error)>)> {
+    let scene = parse_file(path.as_ref())?;
+    let scene_dir = path.as_ref().parent().map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+    let (resolved, failures) = resolve_assets(&scene, &scene_dir, resolver);
+    Ok((scene, resolved, failures))
+}
@@ -0,0 +1,158 @@
+//! Lint-style validation for a parsed [`Scene`]: structural hazards the
+//! `.tdbin` format is prone to (dangling handle references, out-of-range
+//! palette indices, degenerate lights, ...) that parse successfully but would
+//! misbehave or panic further down a consuming tool.
+
+use std::collections::HashSet;
+
+use crate::{format::VERSION, light::Kind as LightKind, Entity, EntityKind, Scene};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub entity_handle: Option<u32>,
+    pub message: String,
+}
+
+/// A single check run over a [`Scene`] by [`Scene::validate`]. Implementors
+/// should push one [`Diagnostic`] per problem found rather than stopping at
+/// the first, so a caller gets a complete report.
+pub trait Rule {
+    fn check(&self, scene: &Scene, sink: &mut Vec<Diagnostic>);
+}
+
+impl<'a> Scene<'a> {
+    #[must_use]
+    pub fn validate(&'a self, rules: &[Box<dyn Rule>]) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for rule in rules {
+            rule.check(self, &mut diagnostics);
+        }
+        diagnostics
+    }
+}
+
+/// Flags `*_handle`/`*_handles` references (vehicle bodies and wheels, joint
+/// shapes, script entities, vehicle vitals and refs) that don't point at any
+/// [`Entity`] actually present in the scene tree.
+pub struct DanglingHandles;
+
+impl DanglingHandles {
+    fn check_handle(
+        handles: &HashSet<u32>,
+        owner: &Entity,
+        referent: &'static str,
+        handle: u32,
+        sink: &mut Vec<Diagnostic>,
+    ) {
+        if !handles.contains(&handle) {
+            sink.push(Diagnostic {
+                severity: Severity::Error,
+                entity_handle: Some(owner.handle),
+                message: format!("{} {} does not refer to any entity in the scene", referent, handle),
+            });
+        }
+    }
+}
+
+impl Rule for DanglingHandles {
+    fn check(&self, scene: &Scene, sink: &mut Vec<Diagnostic>) {
+        let handles: HashSet<u32> = scene.iter_entities().map(|entity| entity.handle).collect();
+        for entity in scene.iter_entities() {
+            match &entity.kind {
+                EntityKind::Vehicle(vehicle) => {
+                    Self::check_handle(&handles, entity, "body_handle", vehicle.body_handle, sink);
+                    for &wheel_handle in &vehicle.wheel_handles {
+                        Self::check_handle(&handles, entity, "wheel_handle", wheel_handle, sink);
+                    }
+                    for &reference in &vehicle.refs {
+                        Self::check_handle(&handles, entity, "ref", reference, sink);
+                    }
+                    for vital in &vehicle.vitals {
+                        Self::check_handle(&handles, entity, "vital body_handle", vital.body_handle, sink);
+                    }
+                }
+                EntityKind::Joint(joint) => {
+                    for &shape_handle in &joint.shape_handles {
+                        Self::check_handle(&handles, entity, "shape_handle", shape_handle, sink);
+                    }
+                }
+                EntityKind::Script(script) => {
+                    for &entity_handle in &script.entity_handles {
+                        Self::check_handle(&handles, entity, "entity_handle", entity_handle, sink);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Flags [`Shape`](crate::Shape)s whose `palette` index doesn't select a
+/// [`Palette`](crate::Palette) that exists in [`Scene::palettes`], which
+/// would make any later `palettes[shape.palette].tinted_material(...)` call
+/// panic.
+pub struct PaletteIndexInRange;
+
+impl Rule for PaletteIndexInRange {
+    fn check(&self, scene: &Scene, sink: &mut Vec<Diagnostic>) {
+        for entity in scene.iter_entities() {
+            if let EntityKind::Shape(shape) = &entity.kind {
+                if shape.palette as usize >= scene.palettes.len() {
+                    sink.push(Diagnostic {
+                        severity: Severity::Error,
+                        entity_handle: Some(entity.handle),
+                        message: format!(
+                            "palette index {} is out of range ({} palettes)",
+                            shape.palette,
+                            scene.palettes.len()
+                        ),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Flags cone lights with a non-positive `cone_angle`, which can't cast any
+/// light.
+pub struct ConeLightAngle;
+
+impl Rule for ConeLightAngle {
+    fn check(&self, scene: &Scene, sink: &mut Vec<Diagnostic>) {
+        for entity in scene.iter_entities() {
+            if let EntityKind::Light(light) = &entity.kind {
+                if light.kind == LightKind::Cone && light.cone_angle <= 0.0 {
+                    sink.push(Diagnostic {
+                        severity: Severity::Warning,
+                        entity_handle: Some(entity.handle),
+                        message: format!("cone light has non-positive cone_angle {}", light.cone_angle),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Flags a [`Scene::version`] that doesn't match the version this build was
+/// written against, as a structured counterpart to the bare `println!` done
+/// while parsing.
+pub struct VersionMatches;
+
+impl Rule for VersionMatches {
+    fn check(&self, scene: &Scene, sink: &mut Vec<Diagnostic>) {
+        if scene.version != VERSION {
+            sink.push(Diagnostic {
+                severity: Severity::Warning,
+                entity_handle: None,
+                message: format!("version mismatch: {:?} != {:?}", scene.version, VERSION),
+            });
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Property test for [`Voxels::from_grid`]'s run-length encoding: encoding
+//! an arbitrary grid of palette indices and decoding the resulting runs
+//! must reproduce the original grid exactly.
+
+use proptest::prelude::*;
+use teardown_bin_format::Voxels;
+
+fn decode_runs(voxels: &Voxels) -> Vec<u8> {
+    voxels
+        .palette_index_runs
+        .chunks_exact(2)
+        .flat_map(|pair| std::iter::repeat(pair[1]).take(pair[0] as usize + 1))
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn round_trips_arbitrary_grids(
+        size in (1u32..6, 1u32..6, 1u32..6),
+        seed in prop::collection::vec(0u8..5, 1..50),
+    ) {
+        let (size_x, size_y, size_z) = size;
+        let volume = (size_x * size_y * size_z) as usize;
+        let indices: Vec<u8> = (0..volume).map(|i| seed[i % seed.len()]).collect();
+
+        let voxels = Voxels::from_grid([size_x, size_y, size_z], indices.iter().copied());
+
+        prop_assert_eq!(decode_runs(&voxels), indices);
+    }
+}
@@ -10,14 +10,26 @@ use indicatif::{ParallelProgressIterator, ProgressBar, ProgressIterator, Progres
 use pyo3::{exceptions, prelude::*, types::PyDict, wrap_pyfunction};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use teardown_bin_format::{
-    light::Kind as LightKind, Entity, EntityKind, EntityKindVariants, Light, Material,
-    MaterialKind, Palette, Rgba, Scene, Shape, Transform,
+    light::Kind as LightKind, BoundaryVertex, Entity, EntityKind, EntityKindVariants, Exhaust,
+    Light, Material, MaterialKind, Palette, Rgba, Scene, Shape, Sound, Transform,
+    TriggerGeometryKind, TriggerSound, VehicleSound, Vital,
 };
 
 struct ImportContext<'a> {
     py: Python<'a>,
     palette_materials: HashMap<u32, HashMap<u8, &'a PyAny>>,
     hash_material_map: HashMap<u64, &'a PyAny>,
+    /// Content-addressed `bpy.data.meshes` data-block per distinct shape, so
+    /// levels with many identical props (crates, barrels, ...) create one
+    /// mesh and link the rest as Blender instances instead of duplicating
+    /// mesh data. Keyed by [`shape_mesh_key`]; persists across the whole
+    /// recursive [`ImportContext::create_object`] walk.
+    mesh_cache: HashMap<u64, &'a PyAny>,
+    /// When set, shapes are imported with palette colors baked into a
+    /// per-loop color attribute (via [`ImportContext::vertex_color_material`])
+    /// instead of getting up to 255 material slots each.
+    vertex_colors: bool,
+    vertex_color_material: &'a PyAny,
     progress_style: ProgressStyle,
     entity_progress: ProgressBar,
     new_light: &'a PyAny,
@@ -27,6 +39,22 @@ struct ImportContext<'a> {
     new_camera: &'a PyAny,
     view_layer: &'a PyAny,
     material_template: &'a PyAny,
+    new_material: &'a PyAny,
+}
+
+/// Key for [`ImportContext::mesh_cache`]: two shapes sharing this key produce
+/// byte-identical mesh geometry, so the second can just reuse the first's
+/// Blender mesh data-block. Includes `texture_tile`/`texture_weight`/
+/// `voxel_scaling` alongside the voxel grid and palette because those are
+/// also baked into the mesh/object the cache entry stands in for.
+fn shape_mesh_key(shape: &Shape) -> u64 {
+    compute_hash_n(&(
+        &shape.voxels,
+        shape.palette,
+        shape.texture_tile,
+        shape.texture_weight.to_bits(),
+        shape.voxel_scaling.to_bits(),
+    ))
 }
 
 /// Polygons all have the same amount of edges
@@ -38,6 +66,15 @@ struct BlenderMeshSpec {
     polygon_loop_total: i32,
     polygon_vert_indices: Vec<i32>,
     polygon_material_index: Option<Vec<i16>>,
+    /// One `(u, v)` pair per polygon loop (same count and winding order as
+    /// `polygon_vert_indices`), written into the mesh's first UV layer.
+    /// Empty means no UV layer is created.
+    polygon_loop_uvs: Vec<f32>,
+    /// One `(r, g, b, a)` tuple per polygon loop (same count and winding
+    /// order as `polygon_vert_indices`), written into a `CORNER`/
+    /// `FLOAT_COLOR` color attribute when [`ImportContext::vertex_colors`]
+    /// is set. Empty means no color attribute is created.
+    loop_colors: Vec<f32>,
 }
 
 impl Default for BlenderMeshSpec {
@@ -48,6 +85,8 @@ impl Default for BlenderMeshSpec {
             edges: None,
             polygon_vert_indices: Vec::new(),
             polygon_material_index: None,
+            polygon_loop_uvs: Vec::new(),
+            loop_colors: Vec::new(),
         }
     }
 }
@@ -91,6 +130,22 @@ impl BlenderMeshSpec {
         if let Some(polygon_material_index) = self.polygon_material_index {
             py_polygons.call_method1("foreach_set", ("material_index", polygon_material_index))?;
         }
+        if !self.polygon_loop_uvs.is_empty() {
+            assert_eq!(self.polygon_loop_uvs.len() as i32, n_polygons * self.polygon_loop_total * 2);
+            let uv_layer = mesh.getattr("uv_layers")?.call_method0("new")?;
+            uv_layer
+                .getattr("data")?
+                .call_method1("foreach_set", ("uv", self.polygon_loop_uvs))?;
+        }
+        if !self.loop_colors.is_empty() {
+            assert_eq!(self.loop_colors.len() as i32, n_polygons * self.polygon_loop_total * 4);
+            let color_attr = mesh
+                .getattr("color_attributes")?
+                .call_method1("new", ("Color", "FLOAT_COLOR", "CORNER"))?;
+            color_attr
+                .getattr("data")?
+                .call_method1("foreach_set", ("color", self.loop_colors))?;
+        }
         let dict = PyDict::new(py);
         // Also calculates loops, so always neccessary
         dict.set_item("calc_edges", true)?;
@@ -109,6 +164,179 @@ pub fn compute_hash_n<H: Hash>(to_hash: &H) -> u64 {
     hasher.finish()
 }
 
+const BASE64_CONFIG: base64::Config = base64::Config::new(base64::CharacterSet::UrlSafe, false);
+
+/// Compactly encodes a hash for storage in a custom property, the same way
+/// `editor_format::hash::n_to_str` does for content-addressed `.vox` names.
+fn n_to_str(n: u64) -> String {
+    base64::encode_config(n.to_le_bytes(), BASE64_CONFIG)
+}
+
+fn transform_to_py<'py>(py: Python<'py>, transform: &Transform) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("pos", transform.pos)?;
+    dict.set_item("rot", transform.rot)?;
+    Ok(dict)
+}
+
+fn sound_to_py<'py>(py: Python<'py>, sound: &Sound) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("path", sound.path)?;
+    dict.set_item("volume", sound.volume)?;
+    Ok(dict)
+}
+
+fn vehicle_sound_to_py<'py>(py: Python<'py>, sound: &VehicleSound) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("name", sound.name)?;
+    dict.set_item("pitch", sound.pitch)?;
+    Ok(dict)
+}
+
+fn trigger_sound_to_py<'py>(py: Python<'py>, sound: &TriggerSound) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("path", sound.path)?;
+    dict.set_item("ramp", sound.ramp)?;
+    dict.set_item("byte", sound.byte)?;
+    dict.set_item("volume", sound.volume)?;
+    Ok(dict)
+}
+
+fn exhaust_to_py<'py>(py: Python<'py>, exhaust: &Exhaust) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("transform", transform_to_py(py, &exhaust.transform)?)?;
+    dict.set_item("z_f32", exhaust.z_f32)?;
+    Ok(dict)
+}
+
+fn vital_to_py<'py>(py: Python<'py>, vital: &Vital) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("body_handle", vital.body_handle)?;
+    dict.set_item("z_f32", vital.z_f32)?;
+    dict.set_item("pos", vital.pos)?;
+    dict.set_item("shape_index", vital.shape_index)?;
+    Ok(dict)
+}
+
+fn boundary_vertex_to_tuple(vertex: &BoundaryVertex) -> (f32, f32) {
+    (vertex.x, vertex.z)
+}
+
+/// Serializes the `Entity` data Blender has no native field for into a
+/// `"teardown"` custom property dict on the object, so a future Blender ->
+/// `.bin` exporter has enough to round-trip: the [`EntityKindVariants`]
+/// discriminant, the raw tags, and every kind-specific parameter not
+/// otherwise represented by `set_transform`/mesh/light attributes. Opaque
+/// blobs ([`EntityKind::Wheel`]'s reserved bytes, an [`EntityKind::Unknown`]'s
+/// raw bytes) are stashed as a compact [`n_to_str`] hash rather than stored
+/// byte-for-byte.
+fn entity_metadata<'py>(py: Python<'py>, entity: &Entity) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("kind", format!("{:?}", EntityKindVariants::from(&entity.kind)))?;
+    dict.set_item("handle", entity.handle)?;
+    dict.set_item("desc", entity.desc)?;
+    let tags = PyDict::new(py);
+    for (key, value) in &entity.tags.0 {
+        tags.set_item(*key, *value)?;
+    }
+    dict.set_item("tags", tags)?;
+    match &entity.kind {
+        EntityKind::Body(body) => {
+            dict.set_item("dynamic", body.dynamic)?;
+            dict.set_item("active", body.active)?;
+        }
+        EntityKind::Light(light) => {
+            dict.set_item("light_kind", format!("{:?}", light.kind))?;
+            dict.set_item("rgba", light.rgba.0)?;
+            dict.set_item("scale", light.scale)?;
+            dict.set_item("reach", light.reach)?;
+            dict.set_item("unshadowed", light.unshadowed)?;
+            dict.set_item("cone_angle", light.cone_angle)?;
+            dict.set_item("cone_penumbra", light.cone_penumbra)?;
+            dict.set_item("glare", light.glare)?;
+            dict.set_item("fog_iter", light.fog_iter)?;
+            dict.set_item("fog_scale", light.fog_scale)?;
+            dict.set_item("area_size", light.area_size)?;
+            dict.set_item("sound", sound_to_py(py, &light.sound)?)?;
+        }
+        EntityKind::Joint(joint) => {
+            dict.set_item("joint_kind", format!("{:?}", joint.kind))?;
+            dict.set_item("shape_handles", joint.shape_handles)?;
+            dict.set_item("connected", joint.connected)?;
+            dict.set_item("collisions", joint.collisions)?;
+            dict.set_item("rot_strength", joint.rot_strength)?;
+            dict.set_item("rot_spring", joint.rot_spring)?;
+            dict.set_item("hinge_min_max", joint.hinge_min_max)?;
+            dict.set_item("size", joint.size)?;
+        }
+        EntityKind::Vehicle(vehicle) => {
+            dict.set_item("velocity", vehicle.velocity)?;
+            dict.set_item("angular_velocity", vehicle.angular_velocity)?;
+            dict.set_item("wheel_handles", vehicle.wheel_handles.clone())?;
+            dict.set_item("max_speed", vehicle.properties.max_speed)?;
+            dict.set_item("spring", vehicle.properties.spring)?;
+            dict.set_item("damping", vehicle.properties.damping)?;
+            dict.set_item("acceleration", vehicle.properties.acceleration)?;
+            dict.set_item("strength", vehicle.properties.strength)?;
+            dict.set_item("friction", vehicle.properties.friction)?;
+            dict.set_item("antispin", vehicle.properties.antispin)?;
+            dict.set_item("steerassist", vehicle.properties.steerassist)?;
+            dict.set_item("antiroll", vehicle.properties.antiroll)?;
+            dict.set_item("sound", vehicle_sound_to_py(py, &vehicle.properties.sound)?)?;
+            dict.set_item("difflock", vehicle.difflock)?;
+            dict.set_item("refs", vehicle.refs.clone())?;
+            dict.set_item(
+                "exhausts",
+                vehicle
+                    .exhausts
+                    .iter()
+                    .map(|exhaust| exhaust_to_py(py, exhaust))
+                    .collect::<PyResult<Vec<_>>>()?,
+            )?;
+            dict.set_item(
+                "vitals",
+                vehicle
+                    .vitals
+                    .iter()
+                    .map(|vital| vital_to_py(py, vital))
+                    .collect::<PyResult<Vec<_>>>()?,
+            )?;
+            dict.set_item("arm_rot", vehicle.arm_rot)?;
+        }
+        EntityKind::Wheel(wheel) => {
+            dict.set_item("blob", n_to_str(compute_hash_n(wheel.z_u8_108)))?;
+        }
+        EntityKind::Trigger(trigger) => {
+            dict.set_item("trigger_kind", format!("{:?}", trigger.type_))?;
+            dict.set_item("sphere_radius", trigger.sphere_radius)?;
+            dict.set_item("half_cuboid", trigger.half_cuboid)?;
+            dict.set_item("polygon_extrusion", trigger.polygon_extrusion)?;
+            dict.set_item(
+                "polygon_vertices",
+                trigger
+                    .polygon_vertices
+                    .iter()
+                    .map(boundary_vertex_to_tuple)
+                    .collect::<Vec<_>>(),
+            )?;
+            dict.set_item("sound", trigger_sound_to_py(py, &trigger.sound)?)?;
+        }
+        EntityKind::Water(water) => {
+            dict.set_item("depth", water.depth)?;
+            dict.set_item("wave", water.wave)?;
+            dict.set_item("ripple", water.ripple)?;
+            dict.set_item("motion", water.motion)?;
+            dict.set_item("foam", water.foam)?;
+        }
+        EntityKind::Unknown { kind_byte, raw } => {
+            dict.set_item("kind_byte", *kind_byte)?;
+            dict.set_item("blob", n_to_str(compute_hash_n(raw)))?;
+        }
+        _ => {}
+    }
+    Ok(dict)
+}
+
 fn get_entity_name(entity: &Entity) -> String {
     let mut s = String::new();
     if !entity.desc.is_empty() {
@@ -122,6 +350,21 @@ fn get_entity_name(entity: &Entity) -> String {
     s
 }
 
+/// The two `[i32; 3]` axis indices a face's quad lies in, so its corners can
+/// be projected to 2D UVs. The face's own normal axis (the one `permutation`
+/// doesn't return here) is constant across the quad and carries no UV
+/// information.
+fn in_plane_axes(permutation: Axis3Permutation) -> (usize, usize) {
+    match permutation {
+        Axis3Permutation::XYZ => (0, 1),
+        Axis3Permutation::XZY => (0, 2),
+        Axis3Permutation::YXZ => (1, 0),
+        Axis3Permutation::YZX => (1, 2),
+        Axis3Permutation::ZXY => (2, 0),
+        Axis3Permutation::ZYX => (2, 1),
+    }
+}
+
 fn set_transform(obj: &PyAny, transform: Option<&Transform>) -> PyResult<()> {
     if let Some(Transform {
         pos,
@@ -136,11 +379,16 @@ fn set_transform(obj: &PyAny, transform: Option<&Transform>) -> PyResult<()> {
 }
 
 impl<'a> ImportContext<'a> {
-    fn create_mesh_for_shape(shape: &Shape, palettes: &[Palette]) -> BlenderMeshSpec {
+    fn create_mesh_for_shape(shape: &Shape, palettes: &[Palette], vertex_colors: bool) -> BlenderMeshSpec {
         let (mut palette_indices, quads) = shape.to_mesh(palettes);
         let mut vert_position_indices: BTreeMap<[i32; 3], i32> = BTreeMap::new();
         let mut polygon_vert_indices: Vec<i32> = Vec::new();
         let mut polygon_material_index: Vec<i16> = Vec::new();
+        let mut polygon_loop_uvs: Vec<f32> = Vec::new();
+        let mut loop_colors: Vec<f32> = Vec::new();
+        let palette = palettes.get(shape.palette as usize);
+        let tile_scale = if shape.texture_tile == 0 { 0.0 } else { 1.0 / shape.texture_tile as f32 };
+        let uv_world_scale = 0.1 * shape.voxel_scaling * tile_scale;
         let mut vert_i = 0;
         for quad_group in &quads.quad_groups {
             for quad in &quad_group.quads {
@@ -160,22 +408,38 @@ impl<'a> ImportContext<'a> {
                     n_sign,
                     ..
                 } = quad_group.face;
-                polygon_vert_indices.extend(
-                    if if permutation == Axis3Permutation::ZXY {
-                        -1
-                    } else {
-                        1
-                    } * n_sign
-                        == 1
-                    {
-                        [2, 3, 1, 0]
-                    } else {
-                        [0, 1, 3, 2]
+                let order: [usize; 4] = if if permutation == Axis3Permutation::ZXY {
+                    -1
+                } else {
+                    1
+                } * n_sign
+                    == 1
+                {
+                    [2, 3, 1, 0]
+                } else {
+                    [0, 1, 3, 2]
+                };
+                polygon_vert_indices.extend(order.iter().map(|rel_i| corner_indices[*rel_i]));
+                let (u_axis, v_axis) = in_plane_axes(permutation);
+                let corner_uvs: [(f32, f32); 4] = corners.map(|corner| {
+                    (
+                        corner.0[u_axis] as f32 * uv_world_scale,
+                        corner.0[v_axis] as f32 * uv_world_scale,
+                    )
+                });
+                polygon_loop_uvs.extend(order.iter().flat_map(|rel_i| {
+                    let (u, v) = corner_uvs[*rel_i];
+                    [u, v]
+                }));
+                let material_index = palette_indices.get_mut(quad.minimum).0;
+                if vertex_colors {
+                    if let Some(material) = palette.and_then(|palette| palette.materials.get(material_index as usize)) {
+                        for _ in 0..4 {
+                            loop_colors.extend_from_slice(&material.rgba.0);
+                        }
                     }
-                    .iter()
-                    .map(|rel_i| corner_indices[*rel_i]),
-                );
-                polygon_material_index.push(i16::from(palette_indices.get_mut(quad.minimum).0));
+                }
+                polygon_material_index.push(i16::from(material_index));
             }
         }
         let verts: Vec<f32> = {
@@ -195,6 +459,8 @@ impl<'a> ImportContext<'a> {
             polygon_loop_total: 4,
             polygon_vert_indices,
             polygon_material_index: Some(polygon_material_index),
+            polygon_loop_uvs,
+            loop_colors,
         }
     }
 
@@ -232,56 +498,151 @@ impl<'a> ImportContext<'a> {
                         light.setattr("spot_size", cone_angle)?;
                         light.setattr("spot_blend", cone_penumbra / cone_angle)?;
                     }
+                    LightKind::Unknown(_) => {
+                        light = self.new_light.call1((name, "POINT"))?;
+                        light.setattr("color", (rgba.0[0], rgba.0[1], rgba.0[2]))?;
+                    }
                 }
                 light.setattr("energy", 100)?;
                 light.setattr("shadow_soft_size", size)?;
                 obj_data = Some(light);
             }
             EntityKind::Shape(shape) => {
-                let blender_mesh = self
-                    .new_mesh
-                    .call1((format!("{} mesh", get_entity_name(entity)),))?;
-                let mesh_obj = self
-                    .new_object
-                    .call1((get_entity_name(entity), blender_mesh))?;
                 if shape.voxels.size.iter().any(|&dim| dim == 0) {
                     println!("Weird thing: {:?}", entity);
                 }
-                if let Some(mesh) = meshes.remove(&entity.handle) {
-                    if mesh.polygon_material_index.as_ref().unwrap().len() > 100 {
-                        let dict = PyDict::new(self.py);
-                        dict.set_item("view_layer", self.view_layer)?;
-                        mesh_obj.call_method("hide_set", (false,), Some(dict))?;
+                let mesh_key = shape_mesh_key(shape);
+                // Computed from this entity's own `BlenderMeshSpec` (one per
+                // entity, built before any `mesh_cache` dedup), not inside
+                // the cache-miss branch below: a cache *hit* still needs to
+                // know whether its (identical) geometry has many polygons,
+                // or every instanced duplicate after the first would skip
+                // the `hide_set` workaround.
+                let mesh_spec = meshes.remove(&entity.handle);
+                let many_polygons = mesh_spec
+                    .as_ref()
+                    .map_or(false, |mesh| mesh.polygon_material_index.as_ref().unwrap().len() > 100);
+                let blender_mesh = if let Some(&cached) = self.mesh_cache.get(&mesh_key) {
+                    cached
+                } else {
+                    let blender_mesh = self
+                        .new_mesh
+                        .call1((format!("{} mesh", get_entity_name(entity)),))?;
+                    if let Some(mesh) = mesh_spec {
+                        mesh.apply_to_mesh(blender_mesh, self.py)?;
+                    }
+                    let mesh_materials = blender_mesh.getattr("materials")?;
+                    if self.vertex_colors {
+                        mesh_materials.call_method1("append", (self.vertex_color_material,))?;
+                    } else {
+                        let mut needs_default_material = true;
+                        let palette = shape.palette;
+                        if let Some(palette_materials) = self.palette_materials.get(&palette) {
+                            let mut none_buffer = Vec::new();
+                            for i in 0..255 {
+                                if let Some(&material) = palette_materials.get(&i) {
+                                    none_buffer.push(Some(material));
+                                    for material in none_buffer {
+                                        needs_default_material = false;
+                                        mesh_materials.call_method1("append", (material,))?;
+                                    }
+                                    none_buffer = Vec::new();
+                                } else {
+                                    none_buffer.push(None);
+                                }
+                            }
+                        }
+                        if needs_default_material {
+                            mesh_materials.call_method1("append", (self.material_template,))?;
+                            mesh_materials.call_method1("append", (self.material_template,))?;
+                        }
                     }
-                    mesh.apply_to_mesh(blender_mesh, self.py)?;
+                    self.mesh_cache.insert(mesh_key, blender_mesh);
+                    blender_mesh
+                };
+                let mesh_obj = self
+                    .new_object
+                    .call1((get_entity_name(entity), blender_mesh))?;
+                if many_polygons {
+                    let dict = PyDict::new(self.py);
+                    dict.set_item("view_layer", self.view_layer)?;
+                    mesh_obj.call_method("hide_set", (false,), Some(dict))?;
                 }
                 mesh_obj.setattr("texture_tile", shape.texture_tile)?;
                 mesh_obj.setattr("texture_weight", shape.texture_weight)?;
                 let s = shape.voxel_scaling * 10.;
                 mesh_obj.setattr("scale", (s, s, s))?;
-                let mesh_materials = blender_mesh.getattr("materials")?;
-                let mut needs_default_material = true;
-                let palette = shape.palette;
-                if let Some(palette_materials) = self.palette_materials.get(&palette) {
-                    let mut none_buffer = Vec::new();
-                    for i in 0..255 {
-                        if let Some(&material) = palette_materials.get(&i) {
-                            none_buffer.push(Some(material));
-                            for material in none_buffer {
-                                needs_default_material = false;
-                                mesh_materials.call_method1("append", (material,))?;
-                            }
-                            none_buffer = Vec::new();
-                        } else {
-                            none_buffer.push(None);
-                        }
+                obj = Some(mesh_obj);
+            }
+            EntityKind::Water(water) => {
+                let min_x = water
+                    .boundary_vertices
+                    .iter()
+                    .map(|v| v.x)
+                    .fold(f32::INFINITY, f32::min);
+                let max_x = water
+                    .boundary_vertices
+                    .iter()
+                    .map(|v| v.x)
+                    .fold(f32::NEG_INFINITY, f32::max);
+                let min_z = water
+                    .boundary_vertices
+                    .iter()
+                    .map(|v| v.z)
+                    .fold(f32::INFINITY, f32::min);
+                let max_z = water
+                    .boundary_vertices
+                    .iter()
+                    .map(|v| v.z)
+                    .fold(f32::NEG_INFINITY, f32::max);
+                let mesh = self.water_plane_mesh(
+                    format!("{} mesh", get_entity_name(entity)),
+                    (min_x, max_x),
+                    (min_z, max_z),
+                )?;
+                obj = Some(self.new_object.call1((get_entity_name(entity), mesh))?);
+            }
+            EntityKind::Trigger(trigger) => {
+                let trigger_obj = self
+                    .new_object
+                    .call1((get_entity_name(entity), None::<&PyAny>))?;
+                match &trigger.type_ {
+                    TriggerGeometryKind::Sphere => {
+                        trigger_obj.setattr("empty_display_type", "SPHERE")?;
+                        trigger_obj.setattr("empty_display_size", trigger.sphere_radius)?;
+                    }
+                    TriggerGeometryKind::Box => {
+                        trigger_obj.setattr("empty_display_type", "CUBE")?;
+                        trigger_obj.setattr("empty_display_size", 1.0)?;
+                        trigger_obj.setattr(
+                            "scale",
+                            (
+                                trigger.half_cuboid[0],
+                                trigger.half_cuboid[1],
+                                trigger.half_cuboid[2],
+                            ),
+                        )?;
+                    }
+                    TriggerGeometryKind::Polygon | TriggerGeometryKind::Unknown(_) => {
+                        trigger_obj.setattr("empty_display_type", "PLAIN_AXES")?;
                     }
                 }
-                if needs_default_material {
-                    mesh_materials.call_method1("append", (self.material_template,))?;
-                    mesh_materials.call_method1("append", (self.material_template,))?;
-                }
-                obj = Some(mesh_obj);
+                obj = Some(trigger_obj);
+            }
+            EntityKind::Location(_) | EntityKind::Joint(_) => {
+                let axis_obj = self
+                    .new_object
+                    .call1((get_entity_name(entity), None::<&PyAny>))?;
+                axis_obj.setattr("empty_display_type", "PLAIN_AXES")?;
+                axis_obj.setattr("empty_display_size", 0.2)?;
+                obj = Some(axis_obj);
+            }
+            EntityKind::Vehicle(_) | EntityKind::Wheel(_) => {
+                let rig_obj = self
+                    .new_object
+                    .call1((get_entity_name(entity), None::<&PyAny>))?;
+                rig_obj.setattr("empty_display_type", "PLAIN_AXES")?;
+                obj = Some(rig_obj);
             }
             _ => {}
         }
@@ -291,6 +652,7 @@ impl<'a> ImportContext<'a> {
             self.new_object.call1((get_entity_name(entity), obj_data))?
         };
         set_transform(obj, entity.kind.transform())?;
+        obj.set_item("teardown", entity_metadata(self.py, entity)?)?;
         collection
             .getattr("objects")?
             .getattr("link")?
@@ -302,6 +664,119 @@ impl<'a> ImportContext<'a> {
         Ok(obj)
     }
 
+    /// Builds a flat quad mesh spanning `x_range`/`z_range` at local `y = 0`,
+    /// standing in for an [`teardown_bin_format::Water`] surface (whose
+    /// `boundary_vertices` only record a 2D footprint; the object's own
+    /// `transform` places it at the right height and orientation).
+    fn water_plane_mesh(
+        &self,
+        name: String,
+        x_range: (f32, f32),
+        z_range: (f32, f32),
+    ) -> PyResult<&'a PyAny> {
+        let (min_x, max_x) = x_range;
+        let (min_z, max_z) = z_range;
+        let mesh = self.new_mesh.call1((name,))?;
+        let verts = vec![
+            (min_x, 0.0, min_z),
+            (max_x, 0.0, min_z),
+            (max_x, 0.0, max_z),
+            (min_x, 0.0, max_z),
+        ];
+        let edges: Vec<(u32, u32)> = Vec::new();
+        let faces = vec![(0_u32, 1_u32, 2_u32, 3_u32)];
+        mesh.call_method1("from_pydata", (verts, edges, faces))?;
+        mesh.call_method0("update")?;
+        Ok(mesh)
+    }
+
+    /// Builds a fresh material named `name` with a Principled BSDF wired up
+    /// from `material`'s fields, instead of `copy()`ing a fixed slider
+    /// template whose node order could silently drift from what we poke.
+    /// Mirrors how Skaterift's `sr_mat` maps [`MaterialKind`] to shader
+    /// presets: `Glass` gets transmission and a near-zero roughness,
+    /// `Unphysical`/`Foliage` get their own roughness/specular presets, and
+    /// any material with a nonzero `emission` gets boosted emission strength
+    /// and shadow casting disabled so it reads as a light source.
+    fn build_material(&self, name: String, material: &Material) -> PyResult<&'a PyAny> {
+        let blender_mat = self.new_material.call1((name,))?;
+        blender_mat.setattr("use_nodes", true)?;
+        let node_tree = blender_mat.getattr("node_tree")?;
+        let nodes = node_tree.getattr("nodes")?;
+        nodes.call_method0("clear")?;
+        let bsdf = nodes.call_method1("new", ("ShaderNodeBsdfPrincipled",))?;
+        let output = nodes.call_method1("new", ("ShaderNodeOutputMaterial",))?;
+        output.setattr("location", (300.0, 0.0))?;
+        node_tree.getattr("links")?.call_method1(
+            "new",
+            (
+                bsdf.getattr("outputs")?.get_item("BSDF")?,
+                output.getattr("inputs")?.get_item("Surface")?,
+            ),
+        )?;
+        let inputs = bsdf.getattr("inputs")?;
+        let Material {
+            rgba: Rgba([r, g, b, alpha]),
+            shinyness,
+            metalness,
+            reflectivity,
+            emission,
+            kind,
+            ..
+        } = material;
+        inputs
+            .get_item("Base Color")?
+            .setattr("default_value", (r, g, b, 1.0))?;
+        inputs.get_item("Alpha")?.setattr("default_value", alpha)?;
+        inputs
+            .get_item("Metallic")?
+            .setattr("default_value", metalness)?;
+        inputs
+            .get_item("Roughness")?
+            .setattr("default_value", 1.0 - shinyness)?;
+        inputs
+            .get_item("Specular")?
+            .setattr("default_value", reflectivity)?;
+        inputs
+            .get_item("Emission")?
+            .setattr("default_value", (r, g, b, 1.0))?;
+        inputs
+            .get_item("Emission Strength")?
+            .setattr("default_value", emission)?;
+        if *alpha < 1.0 {
+            blender_mat.setattr("blend_method", "BLEND")?;
+        }
+        match kind {
+            MaterialKind::Glass => {
+                inputs
+                    .get_item("Transmission")?
+                    .setattr("default_value", 1.0)?;
+                inputs.get_item("Roughness")?.setattr("default_value", 0.0)?;
+                blender_mat.setattr("blend_method", "HASHED")?;
+            }
+            MaterialKind::Unphysical => {
+                inputs.get_item("Specular")?.setattr("default_value", 0.0)?;
+                inputs.get_item("Roughness")?.setattr("default_value", 1.0)?;
+            }
+            MaterialKind::Foliage => {
+                inputs
+                    .get_item("Roughness")?
+                    .setattr("default_value", 0.9)?;
+                inputs
+                    .get_item("Specular")?
+                    .setattr("default_value", 0.1)?;
+            }
+            _ => {}
+        }
+        if *emission > 0.0 {
+            inputs
+                .get_item("Emission Strength")?
+                .setattr("default_value", emission.max(1.0) * 5.0)?;
+            blender_mat.setattr("shadow_method", "NONE")?;
+        }
+        Ok(blender_mat)
+    }
+
     #[allow(clippy::cast_possible_truncation)]
     fn create_palette(&mut self, palette_i: usize, palette: &Palette) {
         let mut index_map: HashMap<u8, &'a PyAny> = HashMap::new();
@@ -318,7 +793,8 @@ impl<'a> ImportContext<'a> {
     }
 
     fn import(&mut self, path: &str) -> PyResult<Py<PyAny>> {
-        let uncompressed = teardown_bin_format::read_to_uncompressed(path)?;
+        let uncompressed = teardown_bin_format::read_to_uncompressed(path)
+            .map_err(|err| PyErr::new::<exceptions::PyException, _>(format!("{:?}", err)))?;
         let parsed = teardown_bin_format::parse_uncompressed(&uncompressed)
             .map_err(|err| PyErr::new::<exceptions::PyException, _>(format!("{:?}", err)))?;
         let mut n_all_entities = 0_usize;
@@ -374,30 +850,8 @@ impl<'a> ImportContext<'a> {
         }
         for hash in material_set {
             let material = hash_to_palette.get(&hash).unwrap();
-            let blender_mat = self.material_template.call_method0("copy")?;
-            blender_mat.setattr("name", format!("{:?}:{:02}", material.kind, hash))?;
-            let sliders = blender_mat
-                .getattr("node_tree")?
-                .getattr("nodes")?
-                .get_item(0)?
-                .getattr("inputs")?;
-            let Material {
-                rgba: Rgba([r, g, b, alpha]),
-                shinyness,
-                metalness,
-                reflectivity,
-                emission,
-                ..
-            } = material;
-            sliders
-                .get_item(0)?
-                .setattr("default_value", (r, g, b, 1.0))?;
-            for (i, value) in [alpha, shinyness, metalness, reflectivity, emission]
-                .iter()
-                .enumerate()
-            {
-                sliders.get_item(i + 1)?.setattr("default_value", **value)?;
-            }
+            let name = format!("{:?}:{:02}", material.kind, hash);
+            let blender_mat = self.build_material(name, material)?;
             self.hash_material_map.insert(hash, blender_mat);
         }
         for (i, palette) in parsed
@@ -418,13 +872,14 @@ impl<'a> ImportContext<'a> {
         let shape_progress = ProgressBar::new(shapes.len() as u64);
         shape_progress.set_style(self.progress_style.clone());
         shape_progress.set_message("Shape mesh preparation");
+        let vertex_colors = self.vertex_colors;
         let mut shape_meshes = shapes
             .par_iter()
             .progress_with(shape_progress)
             .map(|(entity, shape)| {
                 (
                     entity.handle,
-                    Self::create_mesh_for_shape(&shape, &parsed.palettes),
+                    Self::create_mesh_for_shape(&shape, &parsed.palettes, vertex_colors),
                 )
             })
             .collect::<HashMap<_, _>>();
@@ -456,7 +911,8 @@ impl<'a> ImportContext<'a> {
 }
 
 #[pyfunction]
-fn import_as_collection(py: Python, path: &str) -> PyResult<Py<PyAny>> {
+#[args(vertex_colors = "false")]
+fn import_as_collection(py: Python, path: &str, vertex_colors: bool) -> PyResult<Py<PyAny>> {
     let bpy = py.import("bpy")?;
     let bpy_data = bpy.getattr("data")?;
     let progress_style = ProgressStyle::default_bar()
@@ -467,6 +923,11 @@ fn import_as_collection(py: Python, path: &str) -> PyResult<Py<PyAny>> {
     ImportContext {
         palette_materials: HashMap::new(),
         hash_material_map: HashMap::new(),
+        mesh_cache: HashMap::new(),
+        vertex_colors,
+        vertex_color_material: bpy_data
+            .getattr("materials")?
+            .call_method1("get", ("Teardown vertex color template",))?,
         entity_progress: ProgressBar::new(0),
         progress_style,
         py,
@@ -475,6 +936,7 @@ fn import_as_collection(py: Python, path: &str) -> PyResult<Py<PyAny>> {
         material_template: bpy_data
             .getattr("materials")?
             .call_method1("get", ("Teardown template",))?,
+        new_material: bpy_data.getattr("materials")?.getattr("new")?,
         new_object: bpy_data.getattr("objects")?.getattr("new")?,
         new_collection: bpy_data.getattr("collections")?.getattr("new")?,
         new_camera: bpy_data.getattr("cameras")?.getattr("new")?,
@@ -0,0 +1,117 @@
+//! Flattens every [`Shape`] entity's voxel mesh into a single triangle mesh
+//! and writes it as Wavefront OBJ + MTL, so a level can be dragged straight
+//! into Blender without round-tripping through the XML writer's per-entity
+//! tree (OBJ has no parent/child concept of its own, unlike the
+//! [`SceneNode`](crate::doc::SceneNode) tree the other writers share). Each
+//! shape is placed as [`Shape::greedy_mesh`] leaves it: scaled by
+//! `voxel_scaling` and offset by `starting_world_position`. The shape
+//! entity's own `transform` and its parents' transforms (including any
+//! rotation) are not applied.
+
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::Write,
+};
+
+use teardown_bin_format::{EntityKind, Palette, Shape};
+
+use crate::{Result, SceneWriter};
+
+#[derive(Default)]
+struct ObjMesh {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    /// `(palette, palette_index, triangle vertex indices into `positions`/`normals`)`
+    faces: Vec<(u32, u8, [u32; 3])>,
+    materials: BTreeSet<(u32, u8)>,
+}
+
+impl ObjMesh {
+    fn add_shape(&mut self, shape: &Shape, palettes: &[Palette]) {
+        let base = u32::try_from(self.positions.len()).expect("mesh too large for u32 indices");
+        let mesh = shape.greedy_mesh(palettes);
+        self.positions.extend(mesh.positions.iter());
+        self.normals.extend(mesh.normals.iter());
+        for (quad_i, &palette_index) in mesh.palette_indices.iter().enumerate() {
+            let material = (shape.palette, palette_index);
+            self.materials.insert(material);
+            for triangle in mesh.indices[quad_i * 6..quad_i * 6 + 6].chunks_exact(3) {
+                self.faces.push((
+                    material.0,
+                    material.1,
+                    [base + triangle[0], base + triangle[1], base + triangle[2]],
+                ));
+            }
+        }
+    }
+
+    fn write_obj(&self, out: &mut String, mtl_file_name: &str) {
+        out.push_str("mtllib ");
+        out.push_str(mtl_file_name);
+        out.push('\n');
+        for [x, y, z] in &self.positions {
+            out.push_str(&format!("v {} {} {}\n", x, y, z));
+        }
+        for [x, y, z] in &self.normals {
+            out.push_str(&format!("vn {} {} {}\n", x, y, z));
+        }
+        let mut current_material = None;
+        for (palette, palette_index, [a, b, c]) in &self.faces {
+            let material = (*palette, *palette_index);
+            if current_material != Some(material) {
+                out.push_str(&format!("usemtl {}\n", material_name(material.0, material.1)));
+                current_material = Some(material);
+            }
+            // OBJ indices are 1-based
+            out.push_str(&format!(
+                "f {a}//{a} {b}//{b} {c}//{c}\n",
+                a = a + 1,
+                b = b + 1,
+                c = c + 1
+            ));
+        }
+    }
+}
+
+fn material_name(palette: u32, palette_index: u8) -> String {
+    format!("palette{}_{}", palette, palette_index)
+}
+
+impl SceneWriter<'_> {
+    /// Writes `{name}.obj` and `{name}.mtl`: every [`Shape`](EntityKind::Shape)
+    /// entity's voxel mesh, flattened into one baked, hierarchy-free mesh
+    /// with one material per `(palette, palette index)` pair actually used.
+    pub fn write_blender_mesh(&self) -> Result<()> {
+        let mut mesh = ObjMesh::default();
+        for entity in self.scene.iter_entities() {
+            if let EntityKind::Shape(shape) = &entity.kind {
+                mesh.add_shape(shape, &self.scene.palettes);
+            }
+        }
+
+        let obj_name = format!("{}.obj", &self.name);
+        let mtl_name = format!("{}.mtl", &self.name);
+
+        let mut obj = String::new();
+        mesh.write_obj(&mut obj, &mtl_name);
+        File::create(self.mod_dir.join(&obj_name))?.write_all(obj.as_bytes())?;
+
+        let mut mtl = String::new();
+        for &(palette, palette_index) in &mesh.materials {
+            let material = self
+                .scene
+                .palettes
+                .get(palette as usize)
+                .and_then(|palette| palette.materials.get(palette_index as usize));
+            let [r, g, b, a] = material.map_or([1.0, 1.0, 1.0, 1.0], |material| material.rgba.0);
+            mtl.push_str(&format!("newmtl {}\n", material_name(palette, palette_index)));
+            mtl.push_str(&format!("Kd {} {} {}\n", r, g, b));
+            mtl.push_str(&format!("d {}\n", a));
+            mtl.push_str("illum 1\n\n");
+        }
+        File::create(self.mod_dir.join(&mtl_name))?.write_all(mtl.as_bytes())?;
+
+        Ok(())
+    }
+}
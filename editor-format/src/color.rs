@@ -0,0 +1,100 @@
+//! Linear↔sRGB color-space policy threaded through every color-bearing
+//! [`ToXMLAttributes`](crate::xml::attrs::ToXMLAttributes) output, so
+//! lighting/fog/sky/rope tints render consistently in the editor instead of
+//! each hand-rolling (or skipping) gamma correction inline.
+
+use crate::xml::attrs::join_as_strings;
+
+/// Which linear -> sRGB transfer function [`SceneWriter`](crate::SceneWriter)
+/// applies to exported colors, settable as a builder policy.
+#[derive(Debug, Clone, Copy)]
+pub enum ColorPolicy {
+    /// The exact sRGB transfer function (IEC 61966-2-1): `c <= 0.0031308 ?
+    /// 12.92*c : 1.055*c^(1/2.4) - 0.055`.
+    Accurate,
+    /// A single `powf(1/2.2)`-ish approximation, cheaper but slightly off in
+    /// the shadows. This is what `Light::to_xml_attrs` used to hardcode.
+    FastApprox,
+}
+
+impl Default for ColorPolicy {
+    fn default() -> Self {
+        ColorPolicy::Accurate
+    }
+}
+
+impl ColorPolicy {
+    fn encode_channel(self, c: f32) -> f32 {
+        match self {
+            ColorPolicy::Accurate => {
+                if c <= 0.003_130_8 {
+                    12.92 * c
+                } else {
+                    1.055 * c.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            ColorPolicy::FastApprox => c.powf(0.454_545),
+        }
+    }
+
+    /// Gamma-corrects the RGB channels of a space-separated
+    /// [`ToXMLAttributes`](crate::xml::attrs::ToXMLAttributes) color value,
+    /// leaving a trailing alpha channel (a 4th component), if present,
+    /// untouched.
+    pub(crate) fn encode_color_attr(self, raw: &str) -> String {
+        let mut channels: Vec<f32> = raw.split_whitespace().filter_map(|token| token.parse().ok()).collect();
+        for channel in channels.iter_mut().take(3) {
+            *channel = self.encode_channel(*channel);
+        }
+        join_as_strings(channels.iter())
+    }
+}
+
+/// Attribute keys whose value is a linear color that should be gamma
+/// -corrected before being written out. This is deliberately just `color`
+/// (what `Light::to_xml_attrs` always hardcoded, via `.powf(0.454545)`,
+/// before this policy existed) — `fogColor`/`sunColorTint`/`skyboxtint`
+/// were never corrected and are opt-in only, through
+/// [`recolor_environment`], since flipping them on by default would change
+/// the exported XML for any scene with a non-default fog/sky/sun color.
+const COLOR_ATTRS: &[&str] = &["color"];
+
+/// Attribute keys `recolor_environment` gamma-corrects when a caller opts
+/// in, none of which were ever corrected before this policy existed.
+const ENVIRONMENT_COLOR_ATTRS: &[&str] = &["fogColor", "sunColorTint", "skyboxtint"];
+
+/// Routes every `color` attribute in `attrs` through `policy`, in place of
+/// each [`ToXMLAttributes`](crate::xml::attrs::ToXMLAttributes) impl
+/// hand-rolling its own gamma correction.
+pub(crate) fn recolor(attrs: Vec<(&'static str, String)>, policy: ColorPolicy) -> Vec<(&'static str, String)> {
+    recolor_keys(attrs, policy, COLOR_ATTRS)
+}
+
+/// Routes every `fogColor`/`sunColorTint`/`skyboxtint` attribute in `attrs`
+/// through `policy`. Opt-in (see [`SceneWriter::environment_color_policy`](crate::SceneWriter)):
+/// unlike `color`, these attributes were written raw before this policy
+/// existed, so a caller has to ask for this explicitly rather than getting
+/// it unconditionally from [`recolor`].
+pub(crate) fn recolor_environment(
+    attrs: Vec<(&'static str, String)>,
+    policy: ColorPolicy,
+) -> Vec<(&'static str, String)> {
+    recolor_keys(attrs, policy, ENVIRONMENT_COLOR_ATTRS)
+}
+
+fn recolor_keys(
+    attrs: Vec<(&'static str, String)>,
+    policy: ColorPolicy,
+    keys: &[&str],
+) -> Vec<(&'static str, String)> {
+    attrs
+        .into_iter()
+        .map(|(key, value)| {
+            if keys.contains(&key) {
+                (key, policy.encode_color_attr(&value))
+            } else {
+                (key, value)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,61 @@
+//! A format-agnostic document tree that [`crate::WriteEntityContext`] builds
+//! once per entity, so the XML writer in [`crate::xml`] and the YAML writer
+//! in [`crate::yaml`] can serialize the same data without either one
+//! touching [`ToXMLAttributes`](crate::xml::attrs::ToXMLAttributes) or the
+//! entity-walking logic directly.
+
+/// A single attribute value, re-typed from the `(&'static str, String)` pairs
+/// [`ToXMLAttributes`](crate::xml::attrs::ToXMLAttributes) produces, so
+/// serializers can tell a number (or a space-separated sequence of numbers,
+/// e.g. a `pos` or a color) apart from an opaque string without re-parsing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Float(f32),
+    Floats(Vec<f32>),
+}
+
+/// Re-types a [`ToXMLAttributes`](crate::xml::attrs::ToXMLAttributes) value:
+/// splits `raw` on whitespace and, if every token parses as an `f32`, returns
+/// [`Value::Float`] (single token) or [`Value::Floats`] (multiple); falls
+/// back to [`Value::Str`] on any parse failure or empty input, so things like
+/// `file` paths, tag strings, and `"true"`/`"false"` flags pass through
+/// unchanged.
+#[must_use]
+pub fn typed_value(raw: String) -> Value {
+    let tokens: Vec<&str> = raw.split_whitespace().collect();
+    if tokens.is_empty() {
+        return Value::Str(raw);
+    }
+    let parsed: Option<Vec<f32>> = tokens.iter().map(|token| token.parse().ok()).collect();
+    match parsed {
+        Some(mut floats) if floats.len() == 1 => Value::Float(floats.remove(0)),
+        Some(floats) => Value::Floats(floats),
+        None => Value::Str(raw),
+    }
+}
+
+/// One element of the tree: an XML element / YAML mapping with a tag name,
+/// typed attributes, children, and an optional text body. The YAML writer
+/// emits `body` when it is set; the XML writer does not write it.
+#[derive(Debug, Clone, Default)]
+pub struct SceneNode {
+    pub tag: &'static str,
+    pub attrs: Vec<(&'static str, Value)>,
+    pub children: Vec<SceneNode>,
+    pub body: Option<String>,
+}
+
+impl SceneNode {
+    #[must_use]
+    pub fn new(tag: &'static str) -> Self {
+        Self { tag, ..Self::default() }
+    }
+
+    /// Re-types and appends `attrs` as produced by
+    /// [`ToXMLAttributes::to_xml_attrs`](crate::xml::attrs::ToXMLAttributes::to_xml_attrs).
+    pub fn extend_attrs(&mut self, attrs: Vec<(&'static str, String)>) {
+        self.attrs
+            .extend(attrs.into_iter().map(|(key, raw)| (key, typed_value(raw))));
+    }
+}
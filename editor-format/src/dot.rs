@@ -0,0 +1,95 @@
+//! Graphviz DOT export of the entity hierarchy, for visually debugging how
+//! the binary scene's parent/child structure (bodies, wheels skipping their
+//! body child, vehicle parents, joint ropes) maps to what
+//! [`WriteEntityContext::build_entity_node`](crate::WriteEntityContext::build_entity_node)
+//! emits. Run the result through e.g. `dot -Tsvg` to visualize it.
+
+use std::{fs::File, io::Write};
+
+use teardown_bin_format::{Entity, EntityKind, EntityKindVariants, Joint, Rope};
+
+use crate::{name_entity, xml::tags_to_string, Result, SceneWriter};
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn node_color(entity: &Entity) -> &'static str {
+    match &entity.kind {
+        EntityKind::Body(_) => "lightblue",
+        EntityKind::Shape(_) => "khaki",
+        EntityKind::Script(_) => "plum",
+        EntityKind::Vehicle(_) => "orange",
+        EntityKind::Wheel(_) => "gray",
+        EntityKind::Joint(_) => "salmon",
+        EntityKind::Light(_) => "lightyellow",
+        EntityKind::Location(_) => "white",
+        EntityKind::Screen(_) => "lightgreen",
+        EntityKind::Trigger(_) => "lightpink",
+        EntityKind::Water(_) => "lightcyan",
+        EntityKind::Unknown { .. } => "red",
+    }
+}
+
+impl SceneWriter<'_> {
+    /// Writes a Graphviz `.dot` file of the entity hierarchy, as an
+    /// alternative to [`SceneWriter::write_scene`] for visually debugging
+    /// parent/child structure rather than loading it in the editor.
+    pub fn write_dot(&self) -> Result<()> {
+        let mut file = File::create(self.mod_dir.join(format!("{}.dot", &self.name)))?;
+        writeln!(file, "digraph scene {{")?;
+        writeln!(file, "    node [style=filled];")?;
+        for entity in &self.scene.entities {
+            self.write_entity_dot(&mut file, entity, None)?;
+        }
+        writeln!(file, "}}")?;
+        Ok(())
+    }
+
+    fn write_entity_dot(&self, file: &mut File, entity: &Entity, parent: Option<&Entity>) -> Result<()> {
+        let mut label = format!(
+            "#{} {:?}\n{}",
+            entity.handle,
+            EntityKindVariants::from(&entity.kind),
+            name_entity(self.scene, entity)
+        );
+        if !entity.tags.0.is_empty() {
+            label += &format!("\n{}", tags_to_string(&entity.tags));
+        }
+        writeln!(
+            file,
+            "    {} [label=\"{}\", fillcolor={}];",
+            entity.handle,
+            escape(&label),
+            node_color(entity)
+        )?;
+        if let Some(parent) = parent {
+            writeln!(file, "    {} -> {};", parent.handle, entity.handle)?;
+        }
+        if let EntityKind::Joint(Joint { rope: Some(Rope { knots, .. }), .. }) = &entity.kind {
+            if knots.len() >= 2 {
+                self.write_knot_dot(file, entity, "from", &knots[0].from)?;
+                self.write_knot_dot(file, entity, "to", &knots[knots.len() - 1].to)?;
+                for (i, knot) in knots[1..knots.len() - 1].iter().enumerate() {
+                    self.write_knot_dot(file, entity, &format!("between {}", i), &knot.from)?;
+                }
+            }
+        }
+        for child in &entity.children {
+            self.write_entity_dot(file, child, Some(entity))?;
+        }
+        Ok(())
+    }
+
+    fn write_knot_dot(&self, file: &mut File, joint: &Entity, label: &str, pos: &[f32; 3]) -> Result<()> {
+        let node_name = format!("{}_{}", joint.handle, label.replace(' ', "_"));
+        writeln!(
+            file,
+            "    {} [label=\"{}\", shape=point];",
+            node_name,
+            escape(&format!("{} {:?}", label, pos))
+        )?;
+        writeln!(file, "    {} -> {} [style=dashed];", joint.handle, node_name)?;
+        Ok(())
+    }
+}
@@ -1,18 +1,24 @@
 #![feature(array_map, array_chunks, stmt_expr_attributes)]
+mod blender;
+mod dot;
+pub mod doc;
 mod hash;
 mod xml;
+mod yaml;
 // Public
+pub mod color;
 pub mod util;
+pub mod validate;
 pub mod vox;
+pub mod watch;
 
 #[cfg(test)]
 mod tests;
 
 use std::{
+    collections::HashMap,
     f32::consts::TAU,
     fmt::Debug,
-    fs::File,
-    io::Write,
     path::PathBuf,
     sync::{Arc, Mutex},
 };
@@ -22,19 +28,19 @@ use anyhow::Result;
 use derive_builder::Builder;
 use nalgebra::{Isometry3, Point3, Quaternion, UnitQuaternion};
 pub(crate) use quick_xml::Result as XMLResult;
-use quick_xml::{
-    events::{BytesStart, Event},
-    Writer,
+use teardown_bin_format::{
+    Diagnostic, Entity, EntityKind, EntityKindVariants, Joint, Rope, Scene, Transform,
 };
-use teardown_bin_format::{Entity, EntityKind, EntityKindVariants, Joint, Rope, Scene, Transform};
 use thiserror::Error;
 
 use crate::{
+    color::{recolor, ColorPolicy},
+    doc::SceneNode,
     util::IntoFixedArray,
     vox::transform_shape,
     xml::{
         attrs::{join_as_strings, ToXMLAttributes},
-        tags_to_string, WriteXML,
+        boundary_vertex_nodes, compound_child_node, tags_to_string,
     },
 };
 
@@ -55,12 +61,30 @@ pub struct SceneWriter<'a> {
     vox_store: Arc<Mutex<vox::Store>>,
     #[builder(default = "\"main\".into()")]
     name: String,
+    #[builder(default)]
+    color_policy: ColorPolicy,
+    /// Opt-in gamma correction for the `<environment>` element's
+    /// `fogColor`/`sunColorTint`/`skyboxtint` attributes (see
+    /// [`color::recolor_environment`]). `None` (the default) writes them
+    /// raw, matching today's behavior — these were never gamma-corrected
+    /// before `color_policy` existed, unlike per-entity `color` attributes.
+    #[builder(default)]
+    environment_color_policy: Option<ColorPolicy>,
+    /// Opt-in directory for a shared, content-addressed `.vox` asset cache
+    /// (see [`vox::Manifest`]), letting repeated exports across many scenes
+    /// skip rewriting voxel data they've already written to `vox_store`
+    /// before. `None` (the default) disables it, matching today's behavior.
+    #[builder(default)]
+    asset_cache_dir: Option<PathBuf>,
 }
 
 impl SceneWriter<'_> {
-    pub fn write_scene(&self) -> Result<()> {
-        self.xml(self.write_vox()?)?;
-        Ok(())
+    /// Writes this scene's `.vox` assets and `.xml`, returning any
+    /// [`Diagnostic`]s raised along the way (missing palette mappings,
+    /// hidden/empty shapes, renormalized joint rotations, ...) instead of
+    /// just printing them to stderr.
+    pub fn write_scene(&self) -> Result<Vec<Diagnostic>> {
+        self.xml(self.write_vox()?)
     }
 
     fn level_dir(&self) -> PathBuf {
@@ -68,24 +92,59 @@ impl SceneWriter<'_> {
     }
 }
 
-pub(crate) struct WriteEntityContext<'a, W: Write> {
+pub(crate) struct WriteEntityContext<'a> {
     vox: vox::Context<'a>,
     scene: &'a Scene<'a>,
-    writer: &'a mut Writer<W>,
+    color_policy: ColorPolicy,
+    /// Warnings raised while building entity nodes (missing palette
+    /// mappings, missing voxel parts, hidden/empty shapes, renormalized
+    /// joint rotations), accumulated here instead of going straight to
+    /// stderr so a caller can aggregate them — see [`SceneWriter::xml`].
+    diagnostics: Vec<Diagnostic>,
+    /// The `Body` entity owning each shape handle, built once over
+    /// `scene`'s entities so `joint_xml` can look up a joint's shape's body
+    /// in O(1) instead of re-scanning the whole entity tree per joint.
+    shape_handle_bodies: HashMap<u32, &'a Entity<'a>>,
 }
 
-impl WriteEntityContext<'_, &mut File> {
+impl<'a> WriteEntityContext<'a> {
+    pub(crate) fn new(vox: vox::Context<'a>, scene: &'a Scene<'a>, color_policy: ColorPolicy) -> Self {
+        let shape_handle_bodies = scene
+            .iter_entities()
+            .filter(|entity| matches!(entity.kind, EntityKind::Body(_)))
+            .flat_map(|body| body.children.iter().map(move |child| (child.handle, body)))
+            .collect();
+        WriteEntityContext {
+            vox,
+            scene,
+            color_policy,
+            diagnostics: Vec::new(),
+            shape_handle_bodies,
+        }
+    }
+}
+
+/// Builds the [`SceneNode`] for a single [`location`](SceneNode) element
+/// (a joint rope knot), matching the `<location name=".." pos=".."/>` the
+/// XML writer used to emit inline.
+pub(crate) fn location_node(name: &str, pos: &[f32; 3]) -> SceneNode {
+    let mut node = SceneNode::new("location");
+    node.extend_attrs(vec![("name", name.to_string()), ("pos", join_as_strings(pos.iter()))]);
+    node
+}
+
+impl WriteEntityContext<'_> {
     #[allow(clippy::too_many_lines)]
-    pub fn write_entity_xml(
+    pub fn build_entity_node(
         &mut self,
         entity: &Entity,
         parent: Option<&Entity>,
         mut dynamic: bool,
         mut vehicle_parent: bool,
-    ) -> Result<()> {
+    ) -> Result<SceneNode> {
         // debug_write_entity_positions(entity, parent);
         let mut tags = entity.tags.clone();
-        let (name, mut kind_attrs) = match &entity.kind {
+        let (name, kind_attrs) = match &entity.kind {
             EntityKind::Body(body) => {
                 #[rustfmt::skip]
                 // Skip the body in wheels, and write the shape inside directly
@@ -93,7 +152,7 @@ impl WriteEntityContext<'_, &mut File> {
                     if entity.children.len() != 1 {
                         return Err(Error::SingleWheelChild(format!("{:?}", parent)).into());
                     }
-                    return self.write_entity_xml(&entity.children[0], Some(entity), dynamic, vehicle_parent);
+                    return self.build_entity_node(&entity.children[0], Some(entity), dynamic, vehicle_parent);
                 }
                 dynamic = body.dynamic;
                 ("body", body.to_xml_attrs())
@@ -105,7 +164,7 @@ impl WriteEntityContext<'_, &mut File> {
                 ("vehicle", vehicle.to_xml_attrs())
             }
             EntityKind::Wheel(wheel) => ("wheel", wheel.to_xml_attrs()),
-            EntityKind::Joint(joint) => self.joint_xml(joint),
+            EntityKind::Joint(joint) => self.joint_xml(entity, joint),
             EntityKind::Light(light) => {
                 if !light.on {
                     tags.0.insert("turnoff", "");
@@ -117,9 +176,10 @@ impl WriteEntityContext<'_, &mut File> {
             EntityKind::Screen(_) => ("screen", vec![]),
             EntityKind::Trigger(_) => ("trigger", vec![]),
             EntityKind::Water(water) => ("water", water.to_xml_attrs()),
+            EntityKind::Unknown { .. } => ("unknown", vec![]),
         };
-        let start = BytesStart::owned_name(name);
-        let mut attrs = vec![("name", self.name_entity(entity))];
+        let mut node = SceneNode::new(name);
+        node.extend_attrs(vec![("name", name_entity(self.scene, entity))]);
         if let Some(mut world_transform) = corrected_transform(Some(entity)) {
             #[rustfmt::skip]
             let direct_parent_is_vehicle =
@@ -142,113 +202,99 @@ impl WriteEntityContext<'_, &mut File> {
                     world_transform = world_transform_isometry.into()
                 }
             }
-            attrs.append(&mut world_transform.to_xml_attrs());
+            node.extend_attrs(world_transform.to_xml_attrs());
         }
-        attrs.append(&mut entity.to_xml_attrs());
+        node.extend_attrs(entity.to_xml_attrs());
         if !tags.0.is_empty() {
-            attrs.push(("tags", tags_to_string(&tags)));
+            node.extend_attrs(vec![("tags", tags_to_string(&tags))]);
         }
-        attrs.append(&mut kind_attrs);
-        let start = start.with_attributes(attrs.iter().map(|(k, v)| (*k, v.as_ref())));
-        let end = start.to_end().into_owned();
-        self.writer.write_event(Event::Start(start))?;
+        node.extend_attrs(recolor(kind_attrs, self.color_policy));
         for child in &entity.children {
-            self.write_entity_xml(child, Some(entity), dynamic, vehicle_parent)?;
+            node.children
+                .push(self.build_entity_node(child, Some(entity), dynamic, vehicle_parent)?);
         }
         match &entity.kind {
             EntityKind::Water(water) => {
-                water.boundary_vertices.as_slice().write_xml(self.writer)?;
+                node.children.extend(boundary_vertex_nodes(&water.boundary_vertices));
             }
             #[rustfmt::skip]
             EntityKind::Joint(Joint { rope: Some(Rope { knots, .. }), .. }) => {
-                let mut write_loc = |name: &str, pos: &[f32; 3]| {
-                    self.writer.write_event(Event::Empty(
-                        BytesStart::owned_name("location")
-                            .with_attributes(vec![("name", name), ("pos", join_as_strings(pos.iter()).as_str())]),
-                    ))
-                };
                 if knots.len() >= 2 {
-                    write_loc("from", &knots[0].from)?;
-                    write_loc("to", &knots[knots.len()-1].to)?;
+                    node.children.push(location_node("from", &knots[0].from));
+                    node.children.push(location_node("to", &knots[knots.len()-1].to));
                     let between = &knots[1..knots.len()-1];
                     for knot in between {
                         let average = knot.from.iter().zip(knot.to.iter()).map(|(from, to)| (from + to) / 2.0).collect::<Vec<_>>().into_fixed();
-                        write_loc("between", &average)?;
+                        node.children.push(location_node("between", &average));
                     }
                 }
             }
             EntityKind::Shape(shape) => {
                 if let Some(voxels_parts) = self.vox.shape_voxels_parts.get(&entity.handle) {
                     if voxels_parts.len() > 1 {
-                        if let Some(palette_mapping) =
-                            self.vox.palette_mappings.get(shape.palette as usize)
-                        {
-                            let file_attr_value = format!(
-                                "hash/{}.vox",
-                                hash::n_to_str(hash::compute_n(palette_mapping.materials_as_ref()))
-                            );
-                            let file_attr = ("file", file_attr_value.as_str());
-                            for voxels_part in voxels_parts {
-                                Self::write_compound_child(self.writer, voxels_part, file_attr)?;
-                            }
-                        }
+                        let arena = self.vox.shape_arena();
+                        node.children.extend(
+                            voxels_parts
+                                .iter()
+                                .filter_map(|voxels_part| compound_child_node(&arena, shape.palette, voxels_part)),
+                        );
                     }
                 }
             }
             _ => {}
         }
-        self.writer.write_event(Event::End(end))?;
-        Ok(())
+        Ok(node)
     }
+}
 
-    fn is_flashlight(&self, entity: &Entity) -> bool {
-        let last_entity = self.scene.entities.last();
-        last_entity.map_or(false, |last| last.handle == entity.handle)
-    }
+pub(crate) fn is_flashlight(scene: &Scene, entity: &Entity) -> bool {
+    let last_entity = scene.entities.last();
+    last_entity.map_or(false, |last| last.handle == entity.handle)
+}
 
-    fn name_entity(&self, entity: &Entity) -> String {
-        let mut parts = vec![entity.handle.to_string()];
-        match &entity.kind {
-            EntityKind::Shape(shape) => {
-                parts.push(format!("{} voxels", shape.voxels.iter().count()))
-            }
-            EntityKind::Body(body) => {
-                if !body.dynamic {
-                    parts.push("static".into())
-                }
-            }
-            EntityKind::Screen(_) | EntityKind::Trigger(_) | EntityKind::Wheel(_) => {}
-            EntityKind::Water(water) => {
-                parts.push(format!("{} m deep", water.depth));
-            }
-            EntityKind::Vehicle(vehicle) => {
-                if !vehicle.properties.sound.name.is_empty() {}
-                parts.push(vehicle.properties.sound.name.into())
-            }
-            EntityKind::Location(_) => parts.push(tags_to_string(&entity.tags)),
-            EntityKind::Joint(joint) => parts.push(format!("{:?}", joint.kind).to_lowercase()),
-            EntityKind::Script(script) => {
-                let short_path = script
-                    .to_xml_attrs()
-                    .into_iter()
-                    .find_map(|(k, v)| if k == "file" { Some(v) } else { None })
-                    .unwrap_or_default();
-                parts.push(
-                    short_path
-                        .strip_suffix(".lua")
-                        .unwrap_or(&short_path)
-                        .into(),
-                )
+pub(crate) fn name_entity(scene: &Scene, entity: &Entity) -> String {
+    let mut parts = vec![entity.handle.to_string()];
+    match &entity.kind {
+        EntityKind::Shape(shape) => {
+            parts.push(format!("{} voxels", shape.voxels.iter().count()))
+        }
+        EntityKind::Body(body) => {
+            if !body.dynamic {
+                parts.push("static".into())
             }
-            EntityKind::Light(light) => {
-                if self.is_flashlight(entity) {
-                    parts.push("flashlight".into());
-                }
-                parts.push(format!("{:?}", light.kind).to_lowercase())
+        }
+        EntityKind::Screen(_) | EntityKind::Trigger(_) | EntityKind::Wheel(_) => {}
+        EntityKind::Water(water) => {
+            parts.push(format!("{} m deep", water.depth));
+        }
+        EntityKind::Vehicle(vehicle) => {
+            if !vehicle.properties.sound.name.is_empty() {}
+            parts.push(vehicle.properties.sound.name.into())
+        }
+        EntityKind::Location(_) => parts.push(tags_to_string(&entity.tags)),
+        EntityKind::Joint(joint) => parts.push(format!("{:?}", joint.kind).to_lowercase()),
+        EntityKind::Script(script) => {
+            let short_path = script
+                .to_xml_attrs()
+                .into_iter()
+                .find_map(|(k, v)| if k == "file" { Some(v) } else { None })
+                .unwrap_or_default();
+            parts.push(
+                short_path
+                    .strip_suffix(".lua")
+                    .unwrap_or(&short_path)
+                    .into(),
+            )
+        }
+        EntityKind::Light(light) => {
+            if is_flashlight(scene, entity) {
+                parts.push("flashlight".into());
             }
+            parts.push(format!("{:?}", light.kind).to_lowercase())
         }
-        parts.join(" ")
+        EntityKind::Unknown { kind_byte, .. } => parts.push(format!("unknown {}", kind_byte)),
     }
+    parts.join(" ")
 }
 
 #[allow(dead_code)]
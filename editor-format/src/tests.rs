@@ -218,14 +218,44 @@ mod transform_shape {
             }
         )
     }
+
+    #[test]
+    fn round_trips_at_origin_45_45_45() {
+        use crate::vox::inverse_transform_shape;
+
+        let size = [10, 10, 10];
+        let original = Transform {
+            pos: [0.17677675, -0.60355335, 0.32322317],
+            rot: [-0.19134167, 0.19134174, 0.46193975, 0.8446232],
+        };
+        assert_relative_eq!(
+            inverse_transform_shape(&transform_shape(&original, size), size),
+            original
+        )
+    }
+
+    #[test]
+    fn round_trips_positive_x() {
+        use crate::vox::inverse_transform_shape;
+
+        let size = [10, 10, 10];
+        let original = Transform {
+            pos: [1.5, 0.0, 0.5],
+            rot: [-0.7071068, 0.0, 0.0, 0.7071068],
+        };
+        assert_relative_eq!(
+            inverse_transform_shape(&transform_shape(&original, size), size),
+            original
+        )
+    }
 }
 
 mod palette {
-    use teardown_bin_format::{Material, MaterialKind};
+    use teardown_bin_format::{Material, MaterialKind, Rgba};
 
     use crate::{
         util::IntoFixedArray,
-        vox::{remap_materials, PaletteMapping},
+        vox::{quantize_palette, remap_materials, PaletteMapping},
     };
 
     #[test]
@@ -302,6 +332,48 @@ mod palette {
             MaterialKind::Glass
         );
     }
+
+    #[test]
+    fn falls_back_to_quantized_when_a_kind_has_no_room() {
+        // Glass only has 8 slots (1..=8); stuff 9 non-replacable Glass
+        // materials in, none of which fit in their required range.
+        let mut materials: [Material; 256] = vec![Material::default(); 256].into_fixed();
+        for i in 100..109 {
+            materials[i] = Material {
+                replacable: false,
+                kind: MaterialKind::Glass,
+                ..Material::default()
+            };
+        }
+        assert!(matches!(
+            remap_materials(&materials),
+            PaletteMapping::Quantized(_)
+        ));
+    }
+
+    #[test]
+    fn quantize_pins_index_zero() {
+        let mut materials: [Material; 256] = vec![Material::default(); 256].into_fixed();
+        materials[0] = Material {
+            rgba: Rgba([0., 0., 0., 0.]),
+            ..Material::default()
+        };
+        for (i, material) in materials.iter_mut().enumerate().skip(1) {
+            #[allow(clippy::cast_precision_loss)]
+            let t = i as f32 / 255.;
+            material.rgba = Rgba([t, t, t, 1.]);
+        }
+        if let PaletteMapping::Quantized(boxed) = quantize_palette(&materials) {
+            let (quantized, orig_to_quantized) = boxed.as_ref();
+            assert_eq!(orig_to_quantized[0], 0);
+            assert_eq!(quantized[0].rgba.0, materials[0].rgba.0);
+            for &slot in &orig_to_quantized[1..] {
+                assert_ne!(slot, 0);
+            }
+        } else {
+            panic!("should be quantized");
+        }
+    }
 }
 
 mod convert_material {
@@ -464,4 +536,76 @@ mod convert_material {
         assert_eq!(vox_mat.kind, VoxMaterialKind::Glass);
         assert!(vox_mat.alpha.unwrap_or_default() < 1.0);
     }
+
+    #[test]
+    fn round_trips_metal() {
+        use crate::vox::convert_material_back;
+
+        let original = Material {
+            rgba: Rgba([0.2, 0.4, 0.6, 1.0]),
+            reflectivity: 0.5,
+            shinyness: 0.25,
+            metalness: 0.75,
+            ..Material::default()
+        };
+        let round_tripped = convert_material_back(&convert_material(&original));
+        for i in 0..3 {
+            assert_relative_eq!(round_tripped.rgba.0[i], original.rgba.0[i]);
+        }
+        assert_relative_eq!(round_tripped.reflectivity, original.reflectivity);
+        assert_relative_eq!(round_tripped.shinyness, original.shinyness);
+        assert_relative_eq!(round_tripped.metalness, original.metalness);
+    }
+
+    #[test]
+    fn round_trips_glass() {
+        use crate::vox::convert_material_back;
+
+        let original = Material {
+            rgba: Rgba([0.1, 0.2, 0.3, 0.4]),
+            ..Material::default()
+        };
+        let round_tripped = convert_material_back(&convert_material(&original));
+        assert_relative_eq!(round_tripped.rgba.0[3], original.rgba.0[3]);
+    }
+
+    #[test]
+    fn round_trips_emission() {
+        use crate::vox::convert_material_back;
+
+        let original = Material {
+            emission: 50.,
+            ..Material::default()
+        };
+        let round_tripped = convert_material_back(&convert_material(&original));
+        assert_relative_eq!(round_tripped.emission, original.emission);
+    }
+
+    #[test]
+    fn tinted_emitter_keeps_color() {
+        let vox_mat = convert_material(&Material {
+            rgba: Rgba([1.0, 0.2, 0.1, 1.0]),
+            emission: 10.,
+            ..Material::default()
+        });
+        assert_eq!(vox_mat.kind, VoxMaterialKind::Emit);
+        assert_relative_eq!(vox_mat_emission(&vox_mat), 10.);
+        let [r, g, b, _] = vox_mat.rgba;
+        assert!(r > g);
+        assert!(g > b);
+    }
+
+    #[test]
+    fn refractive_glass_pane() {
+        let vox_mat = convert_material(&Material {
+            rgba: Rgba([0.8, 0.9, 1.0, 0.2]),
+            reflectivity: 0.04,
+            ..Material::default()
+        });
+        assert_eq!(vox_mat.kind, VoxMaterialKind::Glass);
+        assert_relative_eq!(vox_mat.trans.unwrap_or_default(), 0.8);
+        // Ordinary glass has an IoR around 1.5.
+        assert!((1.4..1.6).contains(&vox_mat.ior.unwrap_or_default()));
+        assert!(vox_mat.d.unwrap_or_default() > 0.0);
+    }
 }
@@ -0,0 +1,180 @@
+//! Promotes the check/dump-then-compare the disabled `material_test`
+//! integration test used to do by hand (write a `.vox`, re-parse it, diff
+//! materials) into [`SceneWriter::validate_roundtrip`], a reusable API that
+//! re-parses the `.vox` files a scene was just exported to and reports
+//! anything that didn't survive the trip as a structured [`Divergence`]
+//! instead of a `println!`/`assert_eq!`.
+
+use vox::semantic::{Material as VoxMaterial, Node};
+
+use crate::{
+    hash,
+    util::UnwrapLock,
+    vox::{convert_material, PaletteMapping},
+    Result, SceneWriter,
+};
+use teardown_bin_format::EntityKind;
+
+/// One way a shape's exported `.vox` data was found to differ from the
+/// scene it was exported from.
+#[derive(Debug, Clone)]
+pub enum Divergence {
+    /// `key`'s value on the material `palette_index` (in the original
+    /// scene's palette) maps to doesn't match what got written to the
+    /// `.vox` file for `shape_handle`.
+    MaterialField {
+        shape_handle: u32,
+        palette_index: u8,
+        key: &'static str,
+        original: String,
+        exported: String,
+    },
+    /// `shape_handle` uses `palette_index`, but the remapped slot it was
+    /// assigned has no corresponding entry in the exported palette.
+    MissingPaletteMapping { shape_handle: u32, palette_index: u8 },
+    /// `shape_handle`'s exported `.vox` model(s) don't have the same voxel
+    /// count as the source shape.
+    VoxelCountMismatch {
+        shape_handle: u32,
+        original: usize,
+        exported: usize,
+    },
+}
+
+/// The result of [`SceneWriter::validate_roundtrip`]: empty `divergences`
+/// means every shape checked came back from its `.vox` file unchanged.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub divergences: Vec<Divergence>,
+}
+
+impl ValidationReport {
+    #[must_use]
+    pub fn is_lossless(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Formats the fields [`convert_material`] sets, so two [`VoxMaterial`]s can
+/// be diffed key-by-key without requiring it to implement `PartialEq`.
+fn material_fields(material: &VoxMaterial) -> [(&'static str, String); 12] {
+    [
+        ("kind", format!("{:?}", material.kind)),
+        ("rgba", format!("{:?}", material.rgba)),
+        ("alpha", format!("{:?}", material.alpha)),
+        ("trans", format!("{:?}", material.trans)),
+        ("ior", format!("{:?}", material.ior)),
+        ("d", format!("{:?}", material.d)),
+        ("flux", format!("{:?}", material.flux)),
+        ("emit", format!("{:?}", material.emit)),
+        ("metal", format!("{:?}", material.metal)),
+        ("rough", format!("{:?}", material.rough)),
+        ("spec", format!("{:?}", material.spec)),
+        ("weight", format!("{:?}", material.weight)),
+    ]
+}
+
+impl SceneWriter<'_> {
+    /// Writes this scene (the same way [`Self::write_scene`] does), then
+    /// re-parses every `.vox` file it just wrote and walks each shape's used
+    /// palette indices (via [`teardown_bin_format::Shape::iter_voxels`]),
+    /// comparing the exported material at the remapped slot against
+    /// [`convert_material`] run on the original, and the exported voxel
+    /// count against the shape's own. Meant for confirming an export is
+    /// lossless, or pinpointing exactly which material field (or how many
+    /// voxels) drifted.
+    pub fn validate_roundtrip(&self) -> Result<ValidationReport> {
+        let context = self.write_vox()?;
+        // `write_vox` only populates the in-memory `StoreFile`s; they're
+        // written to disk lazily (on `Drop`, or here). Without this, a fresh
+        // hash store has nothing at `vox_path` yet and `parse_file` below
+        // errors out before any divergence can be reported.
+        self.vox_store.unwrap_lock().write_dirty()?;
+        let mut report = ValidationReport::default();
+        for entity in self.scene.iter_entities() {
+            let shape = match &entity.kind {
+                EntityKind::Shape(shape) => shape,
+                _ => continue,
+            };
+            let mapping = match context.palette_mappings.get(shape.palette as usize) {
+                Some(mapping) => mapping,
+                None => continue,
+            };
+            let vox_path = self.vox_store.unwrap_lock().hash_vox_dir.join(format!(
+                "{}.vox",
+                hash::n_to_str(hash::compute_n(mapping.materials_as_ref()))
+            ));
+            let file = vox::semantic::parse_file(&vox_path)?;
+            let nodes = file.root.children().map(Vec::as_slice).unwrap_or(&[]);
+            let exported_palette = file.palette();
+
+            let mut exported_voxel_count = 0;
+            for part in context
+                .shape_voxels_parts
+                .get(&entity.handle)
+                .map(Vec::as_slice)
+                .unwrap_or(&[])
+            {
+                let object_name = hash::n_to_str(hash::compute_n(&part.voxels));
+                let model = nodes
+                    .iter()
+                    .find(|node| node.name.as_deref() == Some(object_name.as_str()))
+                    .and_then(Node::model);
+                exported_voxel_count += model.map_or(0, |model| model.voxels().count());
+            }
+            let original_voxel_count =
+                shape.iter_voxels().filter(|&(_, index)| index != 0).count();
+            if original_voxel_count != exported_voxel_count {
+                report.divergences.push(Divergence::VoxelCountMismatch {
+                    shape_handle: entity.handle,
+                    original: original_voxel_count,
+                    exported: exported_voxel_count,
+                });
+            }
+
+            let mut used_indices: Vec<u8> =
+                shape.iter_voxels().map(|(_, index)| index).collect();
+            used_indices.sort_unstable();
+            used_indices.dedup();
+            for palette_index in used_indices {
+                if palette_index == 0 {
+                    continue;
+                }
+                let new_index = match mapping {
+                    PaletteMapping::Original(_) => palette_index,
+                    PaletteMapping::Remapped(remapped) => remapped.1[palette_index as usize],
+                    PaletteMapping::Quantized(quantized) => {
+                        *quantized.1.get(palette_index as usize).unwrap_or(&0)
+                    }
+                };
+                let exported = match exported_palette.get(new_index as usize - 1) {
+                    Some(material) => material,
+                    None => {
+                        report.divergences.push(Divergence::MissingPaletteMapping {
+                            shape_handle: entity.handle,
+                            palette_index,
+                        });
+                        continue;
+                    }
+                };
+                let expected =
+                    convert_material(&mapping.materials_as_ref()[new_index as usize]);
+                for ((key, original), (_, exported)) in
+                    material_fields(&expected).into_iter().zip(material_fields(exported))
+                {
+                    if original != exported {
+                        report.divergences.push(Divergence::MaterialField {
+                            shape_handle: entity.handle,
+                            palette_index,
+                            key,
+                            original,
+                            exported,
+                        });
+                    }
+                }
+            }
+        }
+        self.xml(context)?;
+        Ok(report)
+    }
+}
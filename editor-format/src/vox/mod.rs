@@ -1,10 +1,10 @@
 use std::{
     borrow::Cow,
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     convert::TryInto,
     f32::consts::TAU,
     fs::{self, File},
-    io::ErrorKind,
+    io::{ErrorKind, Write},
     iter,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
@@ -21,9 +21,14 @@ use vox::semantic::{
 };
 
 use crate::util::{IntoFixedArray, UnwrapLock};
+use crate::vox::quantize::quantize_colors;
+
+mod quantize;
+
 pub struct Store {
     pub hash_vox_dir: PathBuf,
     pub palette_files: HashMap<u64, Arc<Mutex<StoreFile>>>,
+    asset_cache: Option<Arc<Mutex<Manifest>>>,
 }
 
 pub struct StoreFile {
@@ -31,7 +36,58 @@ pub struct StoreFile {
     vox: VoxFile,
     shape_indices: HashMap<u64, usize>,
     dirty: bool,
+    /// Identifies which palette this file was built for, so
+    /// [`StoreFile::store_voxel_data`] can scope `asset_cache` lookups to
+    /// this file's palette: two different palettes can produce the same
+    /// voxel-data hash for an otherwise identical shape, and the manifest is
+    /// shared across every `StoreFile` in a [`Store`].
+    palette_hash: u64,
+}
+
+/// A persistent record of which `(palette_hash, voxel_hash)` pairs have
+/// already been written into a [`Store`]'s shared `.vox` cache on some
+/// previous run, so [`StoreFile::store_voxel_data`] can skip re-serializing
+/// data it already durably wrote out without having to re-open and diff that
+/// data first. Keyed on the pair rather than the bare voxel hash because
+/// this one `Manifest` is shared across every palette's `StoreFile`, and two
+/// different palettes can coincidentally produce the same voxel-data hash
+/// for an unrelated shape. One base64 hash per line, loaded in full on
+/// [`Manifest::load`] and appended to as new hashes are recorded; treated as
+/// an immutable key/value store, so entries are never removed.
+pub(crate) struct Manifest {
+    path: PathBuf,
+    known: HashSet<u64>,
+}
+
+impl Manifest {
+    fn load(cache_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(cache_dir)?;
+        let path = cache_dir.join("manifest.txt");
+        let known = match fs::read_to_string(&path) {
+            Ok(contents) => contents.lines().filter_map(|line| hash::str_to_n(line).ok()).collect(),
+            Err(err) if err.kind() == ErrorKind::NotFound => HashSet::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self { path, known })
+    }
+
+    pub(crate) fn contains(&self, hash_n: u64) -> bool {
+        self.known.contains(&hash_n)
+    }
+
+    /// Records `hash_n` as materialized, appending it to the manifest file
+    /// if it wasn't already known. A no-op if it was, so re-recording an
+    /// already-present hash (e.g. a shape reused within the same scene)
+    /// doesn't grow the file.
+    pub(crate) fn record(&mut self, hash_n: u64) -> Result<()> {
+        if self.known.insert(hash_n) {
+            let mut file = fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+            writeln!(file, "{}", hash::n_to_str(hash_n))?;
+        }
+        Ok(())
+    }
 }
+
 use crate::{hash, Result, SceneWriter};
 
 pub(crate) fn transform_shape(transform: &Transform, mut size_i: [u32; 3]) -> Transform {
@@ -46,15 +102,81 @@ pub(crate) fn transform_shape(transform: &Transform, mut size_i: [u32; 3]) -> Tr
     (pos, rot).into()
 }
 
+/// Undoes [`transform_shape`]'s half-voxel offset and YZX reorientation, so a
+/// transform read back from a `.vox` node can be turned into the Teardown
+/// transform it was derived from.
+pub(crate) fn inverse_transform_shape(transform: &Transform, mut size_i: [u32; 3]) -> Transform {
+    let (pos, rot) = transform.as_nalegbra_pair();
+    size_i = size_i.map(|dim| dim.clamp(0, 256));
+    let size = Vector3::from_iterator(size_i.iter().map(|dim| (dim - dim % 2) as f32));
+    let axis_relative_offset = Vector3::new(0.05, 0.05, 0.0);
+    let axis_offset = size.component_mul(&axis_relative_offset);
+    let rot = rot * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -TAU / 4.);
+    let pos = pos - rot.transform_vector(&axis_offset);
+    (pos, rot).into()
+}
+
 impl StoreFile {
+    /// Loads `path` (if it exists), recomputing [`hash::compute_n`] over
+    /// each loaded node's reconstructed voxel data and comparing it against
+    /// the hash its name claims, instead of trusting a name alone: a
+    /// `write_dirty` interrupted mid-write, or a hash collision, would
+    /// otherwise serve wrong geometry under a correct-looking name forever.
+    /// Nodes that don't match are dropped from the file (so
+    /// [`StoreFile::store_voxel_data`] rewrites fresh, correct data for
+    /// them) and the drop count is logged.
     fn new(path: PathBuf, palette: &[Material; 256]) -> Result<Self> {
+        let palette_hash = hash::compute_n(palette);
         let mut shape_indices = HashMap::new();
+        let mut dirty = false;
         let vox = if path.exists() {
-            let file = vox::semantic::parse_file(&path)?;
-            for (i, child) in file.root.children().unwrap_or(&vec![]).iter().enumerate() {
-                if let Some(name) = &child.name {
-                    if let Ok(hash_n) = hash::str_to_n(name) {
-                        shape_indices.insert(hash_n, i);
+            let mut file = vox::semantic::parse_file(&path)?;
+            // Decide what to drop *before* mutating anything: removing a
+            // child shifts every later index left, so indices collected
+            // during the same pass that deletes would go stale. Only once
+            // the tree is settled do we rebuild `shape_indices` from it.
+            let mismatched_count = file
+                .root
+                .children()
+                .unwrap_or(&vec![])
+                .iter()
+                .filter(|child| {
+                    child.name.as_deref().and_then(|name| hash::str_to_n(name).ok()).map_or(
+                        false,
+                        |hash_n| {
+                            !child
+                                .model()
+                                .map_or(false, |model| hash::compute_n(&voxels_from_model(model)) == hash_n)
+                        },
+                    )
+                })
+                .count();
+            if mismatched_count > 0 {
+                println!(
+                    "{}: dropping {} vox node(s) whose voxel data didn't match their content hash",
+                    path.display(),
+                    mismatched_count,
+                );
+                if let Some(children) = file.root.children_mut() {
+                    children.retain(|child| {
+                        child.name.as_deref().and_then(|name| hash::str_to_n(name).ok()).map_or(
+                            true,
+                            |hash_n| {
+                                child
+                                    .model()
+                                    .map_or(false, |model| hash::compute_n(&voxels_from_model(model)) == hash_n)
+                            },
+                        )
+                    });
+                }
+                dirty = true;
+            }
+            if let Some(children) = file.root.children() {
+                for (i, child) in children.iter().enumerate() {
+                    if let Some(name) = &child.name {
+                        if let Ok(hash_n) = hash::str_to_n(name) {
+                            shape_indices.insert(hash_n, i);
+                        }
                     }
                 }
             }
@@ -71,43 +193,85 @@ impl StoreFile {
             file
         };
         Ok(Self {
-            dirty: false,
+            dirty,
             vox,
             shape_indices,
             path,
+            palette_hash,
         })
     }
 
-    pub(crate) fn store_voxel_data(&mut self, voxel_data: &Voxels) {
+    /// Adds `voxel_data` as a new model in this file, unless it's already
+    /// there (tracked by `shape_indices`, keyed on the voxel-data hash
+    /// alone, since this file has a single palette) or `asset_cache`
+    /// already has it recorded, under its `(palette_hash, voxel_hash)` key, from a
+    /// previous run sharing the same cache directory — in which case this is
+    /// a no-op, trusting the cache's contents are still valid for this
+    /// immutable key. Newly written data is recorded back to `asset_cache`
+    /// so later runs can skip it too. Returns whether a new model was
+    /// actually added, so callers (e.g. [`crate::watch::watch`]) can report
+    /// how much of an export was fresh work versus already up to date.
+    pub(crate) fn store_voxel_data(
+        &mut self,
+        voxel_data: &Voxels,
+        crop_min: [i32; 3],
+        asset_cache: Option<&Arc<Mutex<Manifest>>>,
+    ) -> bool {
         let hash_n = hash::compute_n(&voxel_data);
-        match self.shape_indices.entry(hash_n) {
-            Entry::Vacant(vacancy) => {
-                let len = self.vox.root.children().map(Vec::len).unwrap_or_default();
-                let mut voxels = Vec::new();
-                for (coord, palette_index) in voxel_data.iter() {
-                    if let Ok(pos) = coord
-                        .iter()
-                        .copied()
-                        .map(TryInto::try_into)
-                        .collect::<Result<Vec<_>, _>>()
-                    {
-                        voxels.push(Voxel {
-                            pos: pos.into_fixed(),
-                            index: palette_index,
-                        });
-                    }
-                }
-                let model = Model::new(voxel_data.size.map(|dim| dim.min(256)), voxels);
-                #[allow(clippy::cast_possible_wrap)]
-                let [x, y, z] = model.size().map(|x| (x as i32) / 2);
-                let mut node = Node::new([x, y - 1, z], model);
-                node.name = Some(hash::n_to_str(hash_n));
-                self.vox.root.add(node);
-                self.dirty = true;
-                vacancy.insert(len);
+        if let Some(&i) = self.shape_indices.get(&hash_n) {
+            let verified = self
+                .vox
+                .root
+                .children()
+                .and_then(|children| children.get(i))
+                .and_then(Node::model)
+                .map_or(false, |model| hash::compute_n(&voxels_from_model(model)) == hash_n);
+            if verified {
+                return false;
+            }
+            // The node `shape_indices` points at no longer matches its own
+            // hash (on-disk corruption, or a collision); fall through and
+            // store fresh data instead of silently reusing it.
+            self.shape_indices.remove(&hash_n);
+        }
+        if let Some(asset_cache) = asset_cache {
+            if asset_cache.unwrap_lock().contains(hash::compute_n(&(self.palette_hash, hash_n))) {
+                return false;
+            }
+        }
+        let len = self.vox.root.children().map(Vec::len).unwrap_or_default();
+        let mut voxels = Vec::new();
+        for (coord, palette_index) in voxel_data.iter() {
+            if let Ok(pos) = coord
+                .iter()
+                .copied()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()
+            {
+                voxels.push(Voxel {
+                    pos: pos.into_fixed(),
+                    index: palette_index,
+                });
             }
-            Entry::Occupied(_) => {}
         }
+        let model = Model::new(voxel_data.size.map(|dim| dim.min(256)), voxels);
+        #[allow(clippy::cast_possible_wrap)]
+        let [x, y, z] = model.size().map(|x| (x as i32) / 2);
+        let mut node = Node::new(
+            [x + crop_min[0], y - 1 + crop_min[1], z + crop_min[2]],
+            model,
+        );
+        node.name = Some(hash::n_to_str(hash_n));
+        self.vox.root.add(node);
+        self.dirty = true;
+        self.shape_indices.insert(hash_n, len);
+        if let Some(asset_cache) = asset_cache {
+            asset_cache
+                .unwrap_lock()
+                .record(hash::compute_n(&(self.palette_hash, hash_n)))
+                .expect("while recording asset cache hash");
+        }
+        true
     }
 
     fn write(&mut self) -> Result<()> {
@@ -140,9 +304,25 @@ impl Store {
         Ok(Arc::new(Mutex::new(Self {
             hash_vox_dir: vox_dir.join("hash"),
             palette_files: HashMap::new(),
+            asset_cache: None,
         })))
     }
 
+    /// Opts this store into skipping redundant voxel-data writes across
+    /// separate exports (or separate runs) that share `cache_dir`, by
+    /// consulting a [`Manifest`] persisted there. A no-op if `cache_dir` is
+    /// already the enabled cache's directory.
+    pub(crate) fn enable_asset_cache(&mut self, cache_dir: &Path) -> Result<()> {
+        if self
+            .asset_cache
+            .as_ref()
+            .map_or(true, |cache| cache.unwrap_lock().path.parent() != Some(cache_dir))
+        {
+            self.asset_cache = Some(Arc::new(Mutex::new(Manifest::load(cache_dir)?)));
+        }
+        Ok(())
+    }
+
     pub(crate) fn load_palettes(
         &mut self,
         palettes: &[&[Material; 256]],
@@ -170,6 +350,37 @@ impl Store {
         }
         Ok(())
     }
+
+    /// Re-checks every loaded `.vox` file's nodes against their own content
+    /// hash, the same verification [`StoreFile::new`] does at load time.
+    /// Useful as a standalone health check (e.g. from a CLI subcommand)
+    /// without having to delete and re-import the whole hash store.
+    pub fn verify(&self) -> VerifyReport {
+        let mut report = VerifyReport::default();
+        for file in self.palette_files.values() {
+            let file = file.unwrap_lock();
+            for (&hash_n, &i) in &file.shape_indices {
+                let node = file.vox.root.children().and_then(|children| children.get(i));
+                match node.and_then(Node::model) {
+                    Some(model) if hash::compute_n(&voxels_from_model(model)) == hash_n => {}
+                    Some(_) => report.mismatched.push(file.path.clone()),
+                    None => report.orphaned.push(file.path.clone()),
+                }
+            }
+        }
+        report
+    }
+}
+
+/// The result of [`Store::verify`]: for each `.vox` file, which entries in
+/// its `shape_indices` pointed at a node whose voxel data no longer hashes
+/// to its own name (`mismatched`), or that didn't resolve to a node at all
+/// (`orphaned`). Both should normally be empty; [`StoreFile::new`] already
+/// drops and rebuilds such entries as it loads each file.
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub mismatched: Vec<PathBuf>,
+    pub orphaned: Vec<PathBuf>,
 }
 
 fn iter_material_kinds() -> impl Iterator<Item = MaterialKind> {
@@ -221,7 +432,7 @@ fn range_for_material_kind(material_kind: MaterialKind) -> Option<[u8; 2]> {
         MaterialKind::HardMasonry => [177, 184],
         MaterialKind::Unknown13 => [185, 224],
         MaterialKind::Unphysical => [225, 240],
-        MaterialKind::None => return None,
+        MaterialKind::None | MaterialKind::Unknown(_) => return None,
     })
 }
 
@@ -229,6 +440,11 @@ fn range_for_material_kind(material_kind: MaterialKind) -> Option<[u8; 2]> {
 pub(crate) enum PaletteMapping<'a> {
     Original(&'a [Material; 256]),
     Remapped(Box<([Material; 256], [u8; 256])>),
+    /// A palette built from more colors than fit in 256 slots, perceptually
+    /// quantized down to 256 by [`quantize_palette`]. The `Vec<u8>` maps each
+    /// original (pre-quantization) index to its slot in the quantized
+    /// palette.
+    Quantized(Box<([Material; 256], Vec<u8>)>),
 }
 
 impl PaletteMapping<'_> {
@@ -236,8 +452,45 @@ impl PaletteMapping<'_> {
         match self {
             PaletteMapping::Original(original) => original,
             PaletteMapping::Remapped(remapped) => &remapped.0,
+            PaletteMapping::Quantized(quantized) => &quantized.0,
+        }
+    }
+}
+
+/// Perceptually quantizes `materials` (which may number more than 256) down
+/// to a 256-entry palette, for scenes whose combined voxel colors would
+/// otherwise overflow a single vox palette. Each quantized slot borrows the
+/// non-color fields (kind, reflectivity, etc) of whichever original material
+/// first mapped to it, since those aren't meaningfully averaged.
+///
+/// Index 0 (Teardown's "no voxel" slot) is pinned to slot 0 and excluded
+/// from quantization, matching [`PaletteMapping::Original`]/`Remapped`,
+/// whose vox-index arithmetic (`exported_palette[new_index - 1]`, see
+/// `validate.rs`) assumes slot 0 is never a legitimate color.
+pub(crate) fn quantize_palette(materials: &[Material]) -> PaletteMapping {
+    let mut orig_to_quantized = vec![0_u8; materials.len()];
+    let mut new_materials: [Material; 256] = vec![Material::default(); 256].into_fixed();
+    if let Some(no_voxel) = materials.first() {
+        new_materials[0] = no_voxel.clone();
+    }
+    if materials.len() > 1 {
+        let colors: Vec<Rgba> = materials[1..].iter().map(|material| material.rgba.clone()).collect();
+        let (quantized_colors, colors_to_quantized) = quantize_colors(&colors);
+        let mut filled = [false; 255];
+        for (color_i, &slot) in colors_to_quantized.iter().enumerate() {
+            let slot = slot as usize;
+            let orig_i = color_i + 1;
+            orig_to_quantized[orig_i] = slot as u8 + 1;
+            if !filled[slot] {
+                filled[slot] = true;
+                new_materials[slot + 1] = Material {
+                    rgba: quantized_colors[slot].clone(),
+                    ..materials[orig_i].clone()
+                };
+            }
         }
     }
+    PaletteMapping::Quantized(Box::new((new_materials, orig_to_quantized)))
 }
 
 fn try_swap_index(
@@ -325,12 +578,21 @@ pub(crate) fn remap_materials(orig_palette: &[Material; 256]) -> PaletteMapping
         .collect::<Vec<_>>()
         .into_fixed();
     if !overflowed.is_empty() {
+        // Every material kind needs its materials at specific indices, but
+        // this palette has more materials of some kind than that kind has
+        // slots for: there's no permutation of the original 256 materials
+        // that puts them all where they belong. Rather than keep the
+        // structurally "correct" index and silently wear someone else's
+        // color (what the fallback above does), fall back to perceptually
+        // quantizing the whole palette so every material at least gets a
+        // close representative color.
         warn_wrong_indices(
             overflowed.as_ref(),
             &orig_palette,
             &new_palette,
             &orig_to_new,
-        )
+        );
+        return quantize_palette(orig_palette);
     }
     PaletteMapping::Remapped(Box::new((new_palette, orig_to_new)))
 }
@@ -361,6 +623,14 @@ fn warn_wrong_indices(
     )
 }
 
+/// Reflectivity at normal incidence (the Fresnel `F0` term) implies an index
+/// of refraction: `r = ((n - 1) / (n + 1))^2`, so `n = (1 + sqrt(r)) / (1 -
+/// sqrt(r))`. Ordinary glass has `r` around 0.04, giving an IoR near 1.5.
+fn ior_from_reflectivity(reflectivity: f32) -> f32 {
+    let sqrt_r = reflectivity.clamp(0., 0.99).sqrt();
+    (1. + sqrt_r) / (1. - sqrt_r)
+}
+
 pub(crate) fn convert_material(material: &Material) -> VoxMaterial {
     let Rgba([r, g, b, alpha]) = material.rgba;
     #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
@@ -368,6 +638,12 @@ pub(crate) fn convert_material(material: &Material) -> VoxMaterial {
         VoxMaterial::new_color([r, g, b, alpha].map(|comp| (comp * 255.).clamp(0., 255.) as u8));
     vox_mat.kind = if vox_mat.rgba[3] < 255 {
         vox_mat.alpha = Some(alpha);
+        vox_mat.trans = Some(1.0 - alpha);
+        vox_mat.ior = Some(ior_from_reflectivity(material.reflectivity));
+        // Thicker/denser-looking glass scatters more of the light passing
+        // through it, so tie the media density to how little light the
+        // alpha channel already lets through.
+        vox_mat.d = Some((1.0 - alpha).max(0.01));
         VoxMaterialKind::Glass
     } else if material.emission > 0.0 {
         let e = material.emission;
@@ -385,6 +661,9 @@ pub(crate) fn convert_material(material: &Material) -> VoxMaterial {
         };
         vox_mat.flux = Some(flux);
         vox_mat.emit = Some(e / 10_f32.powf(flux - 1.));
+        // `vox_mat.rgba` (set above via `new_color`) already carries the
+        // material's color, so the emitted light keeps the same tint as the
+        // surface rather than emitting as flat white.
         VoxMaterialKind::Emit
     } else {
         vox_mat.metal = Some(material.metalness);
@@ -396,12 +675,159 @@ pub(crate) fn convert_material(material: &Material) -> VoxMaterial {
     vox_mat
 }
 
+/// Inverts [`convert_material`]: recovers Teardown's reflectivity/shininess/
+/// metalness/emission/alpha fields from a vox material. The resulting
+/// `MaterialKind` is always `None`, since `MaterialKind` is not recoverable
+/// from a vox material alone.
+pub(crate) fn convert_material_back(vox_mat: &VoxMaterial) -> Material {
+    #[allow(clippy::cast_precision_loss)]
+    let channel = |c: u8| f32::from(c) / 255.;
+    let [r, g, b, a] = vox_mat.rgba;
+    let mut material = Material {
+        rgba: Rgba([channel(r), channel(g), channel(b), 1.0]),
+        ..Material::default()
+    };
+    match vox_mat.kind {
+        VoxMaterialKind::Glass => {
+            material.rgba.0[3] = vox_mat
+                .alpha
+                .or_else(|| vox_mat.trans.map(|trans| 1.0 - trans))
+                .unwrap_or_else(|| channel(a));
+            material.reflectivity = vox_mat
+                .ior
+                .map_or(material.reflectivity, |ior| ((ior - 1.) / (ior + 1.)).powi(2));
+        }
+        VoxMaterialKind::Emit => {
+            let flux = vox_mat.flux.unwrap_or(1.);
+            let emit = vox_mat.emit.unwrap_or_default();
+            material.emission = emit * 10_f32.powf(flux - 1.);
+        }
+        VoxMaterialKind::Metal => {
+            material.metalness = vox_mat.metal.unwrap_or_default();
+            material.shinyness = 1.0 - vox_mat.rough.unwrap_or_default();
+            material.reflectivity = vox_mat.spec.unwrap_or_default();
+        }
+    }
+    material
+}
+
+/// A shape read back from a MagicaVoxel model, ready to be grafted onto a
+/// Teardown `Entity::Shape` by a caller that supplies the remaining
+/// Teardown-specific fields (density, transform, etc).
+pub struct ImportedShape {
+    pub voxels: Voxels<'static>,
+    pub materials: [Material; 256],
+}
+
+/// Reconstructs dense, run-length-encoded [`Voxels`] from a MagicaVoxel
+/// [`Model`]'s sparse voxel list, the inverse of the dense-grid walk
+/// `write_vox` uses to build a `Model` in the first place.
+fn voxels_from_model(model: &Model) -> Voxels<'static> {
+    let size = model.size();
+    let volume = size.iter().product::<u32>() as usize;
+    let mut indices = vec![0_u8; volume];
+    for voxel in model.voxels() {
+        let [x, y, z] = voxel.pos;
+        #[allow(clippy::cast_sign_loss)]
+        let i = (z as usize * size[1] as usize + y as usize) * size[0] as usize + x as usize;
+        if let Some(slot) = indices.get_mut(i) {
+            *slot = voxel.index;
+        }
+    }
+    Voxels {
+        size,
+        palette_index_runs: Cow::Owned(run_length_encode(indices.into_iter())),
+    }
+}
+
+/// Reads a MagicaVoxel model at `path` and converts its first model and
+/// palette back into Teardown voxels/materials, inverting [`convert_material`]
+/// for every palette entry. This is the counterpart to the shapes-to-vox path
+/// that `write_vox` drives.
+pub fn import_vox<P: AsRef<Path>>(path: P) -> Result<ImportedShape> {
+    let file = vox::semantic::parse_file(path)?;
+    let model = file
+        .root
+        .children()
+        .and_then(|children| children.first())
+        .and_then(Node::model)
+        .ok_or_else(|| anyhow::anyhow!("vox file has no model to import"))?;
+    let voxels = voxels_from_model(model);
+    let mut materials: [Material; 256] = vec![Material::default(); 256].into_fixed();
+    for (i, vox_material) in file.palette().iter().enumerate().take(255) {
+        materials[i + 1] = convert_material_back(vox_material);
+    }
+    Ok(ImportedShape { voxels, materials })
+}
+
 /// Partial result of Voxels being split
 pub(crate) struct VoxelsPart<'a> {
     pub relative_pos: [u32; 3],
     pub voxels: Voxels<'a>,
 }
 
+/// One axis of a bounding box that starts empty and is grown one position at
+/// a time by [`Dimension::include`], used by [`crop_voxels`] to find the
+/// tight box around a shape's non-zero voxels.
+#[derive(Debug, Clone, Copy, Default)]
+struct Dimension {
+    offset: i32,
+    size: u32,
+}
+
+impl Dimension {
+    /// Grows this dimension, if needed, so `pos` falls inside it.
+    fn include(&mut self, pos: i32) {
+        if self.size == 0 {
+            self.offset = -pos;
+            self.size = 1;
+            return;
+        }
+        let min = -self.offset;
+        let max = min + self.size as i32 - 1;
+        self.offset = self.offset.max(-pos);
+        #[allow(clippy::cast_sign_loss)]
+        let size = max.max(pos) - min.min(pos) + 1;
+        self.size = size as u32;
+    }
+}
+
+/// Crops `voxels` to the tight axis-aligned bounding box of its non-zero
+/// voxels, so hollow or sparsely-filled shapes (buildings, thin walls) don't
+/// waste model volume and index-run bytes on their empty margins. Returns
+/// the crop's min corner — which must be added back into a
+/// [`VoxelsPart::relative_pos`] (or a stored model's own node translation)
+/// for the crop to not shift where the voxels end up placed — alongside the
+/// cropped [`Voxels`]. `None` if `voxels` has no non-zero voxels at all: a
+/// shape like that should produce no model.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn crop_voxels(voxels: &Voxels) -> Option<([i32; 3], Voxels<'static>)> {
+    let mut dims = [Dimension::default(); 3];
+    for (pos, _) in voxels.iter() {
+        for (dim, &p) in dims.iter_mut().zip(pos.iter()) {
+            dim.include(p);
+        }
+    }
+    if dims.iter().any(|dim| dim.size == 0) {
+        return None;
+    }
+    let min = dims.map(|dim| -dim.offset);
+    let size = dims.map(|dim| dim.size);
+    let mut indices = vec![0_u8; size.iter().product::<u32>() as usize];
+    for (pos, palette_index) in voxels.iter() {
+        let local = [pos[0] - min[0], pos[1] - min[1], pos[2] - min[2]].map(|dim| dim as usize);
+        let i = (local[2] * size[1] as usize + local[1]) * size[0] as usize + local[0];
+        indices[i] = palette_index;
+    }
+    Some((
+        min,
+        Voxels {
+            size,
+            palette_index_runs: Cow::Owned(run_length_encode(indices.into_iter())),
+        },
+    ))
+}
+
 fn run_length_encode(mut byte_iter: impl Iterator<Item = u8>) -> Vec<u8> {
     let mut encoded = Vec::new();
     let mut n: u8 = 0;
@@ -519,6 +945,55 @@ fn split_voxels<const MAX: usize>(voxels: Voxels) -> Vec<VoxelsPart> {
 pub(crate) struct Context<'a> {
     pub palette_mappings: Vec<PaletteMapping<'a>>,
     pub shape_voxels_parts: HashMap<u32, Vec<VoxelsPart<'a>>>,
+    /// `object=` names of every voxel model this [`SceneWriter::write_vox`]
+    /// call actually (re)wrote, as opposed to finding already present (via
+    /// `StoreFile`'s own dedup or a shared [`Manifest`]). Lets a caller like
+    /// [`crate::watch::watch`] report how much of an export was fresh work.
+    pub written_objects: Vec<String>,
+}
+
+impl<'a> Context<'a> {
+    /// Borrows this context's palette mappings as a [`ShapeArena`], so
+    /// entity-writing code looks up `file=`/`object=` references through one
+    /// shared interning path instead of hashing palettes and voxel data ad
+    /// hoc at each call site.
+    pub(crate) fn shape_arena(&self) -> ShapeArena<'a, '_> {
+        ShapeArena { palette_mappings: &self.palette_mappings }
+    }
+}
+
+/// The `file=`/`object=` attribute pair identifying one interned shape: which
+/// shared `.vox` it lives in, and which model inside that file is it.
+pub(crate) struct ShapeRef {
+    pub file: String,
+    pub object: String,
+}
+
+/// Interns shapes by content hash of `(voxels, palette materials)`, so
+/// entities that reuse the same voxel data and palette reference one shared
+/// `.vox` model instead of each re-serializing it. The "stable id" an arena
+/// would normally track explicitly is, here, the content hash itself:
+/// interning the same content always recomputes the same id, so first and
+/// subsequent encounters need no separate bookkeeping in this type — the
+/// actual write-once-per-id behavior lives in [`StoreFile::store_voxel_data`],
+/// which this is a read-side front-end for.
+pub(crate) struct ShapeArena<'a, 'b> {
+    palette_mappings: &'b [PaletteMapping<'a>],
+}
+
+impl ShapeArena<'_, '_> {
+    /// Looks up the stable `file=`/`object=` reference for `voxels` under
+    /// palette index `palette`, or `None` if `palette` is out of range.
+    pub(crate) fn intern(&self, palette: u32, voxels: &Voxels) -> Option<ShapeRef> {
+        let palette_mapping = self.palette_mappings.get(palette as usize)?;
+        Some(ShapeRef {
+            file: format!(
+                "hash/{}.vox",
+                hash::n_to_str(hash::compute_n(palette_mapping.materials_as_ref()))
+            ),
+            object: hash::n_to_str(hash::compute_n(voxels)),
+        })
+    }
 }
 
 impl SceneWriter<'_> {
@@ -530,6 +1005,10 @@ impl SceneWriter<'_> {
                 return Err(err.into());
             }
         }
+        if let Some(asset_cache_dir) = &self.asset_cache_dir {
+            self.vox_store.unwrap_lock().enable_asset_cache(asset_cache_dir)?;
+        }
+        let asset_cache = self.vox_store.unwrap_lock().asset_cache.clone();
         #[rustfmt::skip]
         let palette_mappings = self.scene.palettes.iter()
             .map(|palette| remap_materials(&palette.materials))
@@ -543,7 +1022,7 @@ impl SceneWriter<'_> {
                     .collect::<Vec<_>>()
                     .as_ref(),)?
         };
-        let mut palette_voxel_data: Vec<Vec<Voxels>> = iter::repeat(Vec::new())
+        let mut palette_voxel_data: Vec<Vec<([i32; 3], Voxels)>> = iter::repeat(Vec::new())
             .take(self.scene.palettes.len())
             .collect();
         let mut shape_voxels_parts: HashMap<u32, Vec<VoxelsPart>> = HashMap::new();
@@ -562,34 +1041,56 @@ impl SceneWriter<'_> {
                     }
                     voxels.palette_index_runs = Cow::Owned(palette_index_runs);
                 }
-                let voxels_parts = split_voxels::<256>(voxels);
+                if let Some(PaletteMapping::Quantized(quantized)) =
+                    palette_mappings.get(shape.palette as usize)
+                {
+                    let mut palette_index_runs = voxels.palette_index_runs.into_owned();
+                    for [_n_times, palette_index] in palette_index_runs.array_chunks_mut() {
+                        *palette_index = quantized.1[*palette_index as usize];
+                    }
+                    voxels.palette_index_runs = Cow::Owned(palette_index_runs);
+                }
+                let Some((crop_min, cropped)) = crop_voxels(&voxels) else {
+                    continue;
+                };
+                #[allow(clippy::cast_sign_loss)]
+                let crop_min_u = crop_min.map(|dim| dim as u32);
+                let mut voxels_parts = split_voxels::<256>(cropped);
+                for part in &mut voxels_parts {
+                    for (pos, min) in part.relative_pos.iter_mut().zip(crop_min_u) {
+                        *pos += min;
+                    }
+                }
                 palette_voxel_data
                     .get_mut(shape.palette as usize)
                     .expect("non-existent palette")
                     .extend(
                         voxels_parts
                             .iter()
-                            .map(|voxel_part| voxel_part.voxels.clone()),
+                            .map(|voxel_part| (crop_min, voxel_part.voxels.clone())),
                     );
                 shape_voxels_parts.insert(entity.handle, voxels_parts);
             }
         }
         #[rustfmt::skip]
-        palette_files.into_iter()
+        let written_objects = palette_files.into_iter()
             .zip(palette_voxel_data)
             .par_bridge()
-            .for_each(|(palette_file, voxel_data)| {
-                voxel_data.par_iter().for_each_with(
+            .flat_map(|(palette_file, voxel_data)| {
+                voxel_data.par_iter().filter_map_with(
                     palette_file,
-                    |palette_file, shape_voxel_data| {
-                        palette_file.unwrap_lock()
-                            .store_voxel_data(&shape_voxel_data)
+                    |palette_file, (crop_min, shape_voxel_data)| {
+                        let wrote = palette_file.unwrap_lock()
+                            .store_voxel_data(shape_voxel_data, *crop_min, asset_cache.as_ref());
+                        wrote.then(|| hash::n_to_str(hash::compute_n(shape_voxel_data)))
                     },
                 )
-            });
+            })
+            .collect();
         Ok(Context {
             palette_mappings,
             shape_voxels_parts,
+            written_objects,
         })
     }
 }
@@ -0,0 +1,258 @@
+//! Perceptual palette quantization, used when a scene's combined voxel colors
+//! exceed a single 256-slot vox palette. Colors are quantized in CIELAB
+//! rather than raw sRGB so near-gradient colors don't band as harshly.
+
+use teardown_bin_format::Rgba;
+
+type Lab = [f32; 3];
+
+fn srgb_channel_to_linear(c: f32) -> f32 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_channel_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// sRGB (D65) <-> CIE XYZ
+fn linear_to_xyz([r, g, b]: [f32; 3]) -> [f32; 3] {
+    [
+        0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b,
+        0.212_672_9 * r + 0.715_152_2 * g + 0.072_175_0 * b,
+        0.019_333_9 * r + 0.119_192_0 * g + 0.950_304_1 * b,
+    ]
+}
+
+fn xyz_to_linear([x, y, z]: [f32; 3]) -> [f32; 3] {
+    [
+        3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z,
+        -0.969_266_0 * x + 1.876_010_8 * y + 0.041_556_0 * z,
+        0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z,
+    ]
+}
+
+// D65 reference white
+const WHITE: [f32; 3] = [0.950_47, 1.0, 1.088_83];
+const DELTA: f32 = 6. / 29.;
+
+fn lab_f(t: f32) -> f32 {
+    if t > DELTA.powi(3) {
+        t.cbrt()
+    } else {
+        t / (3. * DELTA * DELTA) + 4. / 29.
+    }
+}
+
+fn lab_f_inv(t: f32) -> f32 {
+    if t > DELTA {
+        t.powi(3)
+    } else {
+        3. * DELTA * DELTA * (t - 4. / 29.)
+    }
+}
+
+fn xyz_to_lab([x, y, z]: [f32; 3]) -> Lab {
+    let fx = lab_f(x / WHITE[0]);
+    let fy = lab_f(y / WHITE[1]);
+    let fz = lab_f(z / WHITE[2]);
+    [116. * fy - 16., 500. * (fx - fy), 200. * (fy - fz)]
+}
+
+fn lab_to_xyz([l, a, b]: Lab) -> [f32; 3] {
+    let fy = (l + 16.) / 116.;
+    let fx = fy + a / 500.;
+    let fz = fy - b / 200.;
+    [
+        lab_f_inv(fx) * WHITE[0],
+        lab_f_inv(fy) * WHITE[1],
+        lab_f_inv(fz) * WHITE[2],
+    ]
+}
+
+fn rgba_to_lab(rgba: &Rgba) -> Lab {
+    let [r, g, b, _] = rgba.0;
+    xyz_to_lab(linear_to_xyz([
+        srgb_channel_to_linear(r),
+        srgb_channel_to_linear(g),
+        srgb_channel_to_linear(b),
+    ]))
+}
+
+fn lab_to_rgba(lab: Lab, alpha: f32) -> Rgba {
+    let [r, g, b] = xyz_to_linear(lab_to_xyz(lab));
+    Rgba([
+        linear_channel_to_srgb(r).clamp(0., 1.),
+        linear_channel_to_srgb(g).clamp(0., 1.),
+        linear_channel_to_srgb(b).clamp(0., 1.),
+        alpha,
+    ])
+}
+
+fn lab_dist2(a: Lab, b: Lab) -> f32 {
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum()
+}
+
+struct MedianCutBox {
+    indices: Vec<usize>,
+}
+
+fn axis_spread(points: &[Lab], indices: &[usize], axis: usize) -> f32 {
+    let (min, max) = indices.iter().fold((f32::MAX, f32::MIN), |(min, max), &i| {
+        let v = points[i][axis];
+        (min.min(v), max.max(v))
+    });
+    max - min
+}
+
+fn widest_axis(points: &[Lab], indices: &[usize]) -> usize {
+    (0..3)
+        .max_by(|&a, &b| {
+            axis_spread(points, indices, a)
+                .partial_cmp(&axis_spread(points, indices, b))
+                .expect("spread is never NaN")
+        })
+        .unwrap_or(0)
+}
+
+/// Quantizes `colors` (which may number more than 256) into at most 256
+/// representative colors by repeatedly splitting the box with the greatest
+/// axis spread at its median, in CIELAB space. Returns the representative
+/// palette plus, for each input color, the index of its nearest
+/// representative (by squared Lab distance).
+pub(crate) fn quantize_colors(colors: &[Rgba]) -> (Vec<Rgba>, Vec<u8>) {
+    if colors.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    let points: Vec<Lab> = colors.iter().map(rgba_to_lab).collect();
+    let mut boxes = vec![MedianCutBox {
+        indices: (0..colors.len()).collect(),
+    }];
+    while boxes.len() < 256 {
+        let splittable = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, box_)| box_.indices.len() > 1)
+            .max_by(|(_, a), (_, b)| {
+                let spread =
+                    |box_: &MedianCutBox| axis_spread(&points, &box_.indices, widest_axis(&points, &box_.indices));
+                spread(a)
+                    .partial_cmp(&spread(b))
+                    .expect("spread is never NaN")
+            })
+            .map(|(i, _)| i);
+        let Some(split_i) = splittable else { break };
+        let box_ = boxes.remove(split_i);
+        let axis = widest_axis(&points, &box_.indices);
+        let mut sorted = box_.indices;
+        sorted.sort_by(|&a, &b| {
+            points[a][axis]
+                .partial_cmp(&points[b][axis])
+                .expect("lab component is never NaN")
+        });
+        let mid = sorted.len() / 2;
+        let (low, high) = sorted.split_at(mid);
+        boxes.push(MedianCutBox {
+            indices: low.to_vec(),
+        });
+        boxes.push(MedianCutBox {
+            indices: high.to_vec(),
+        });
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let palette: Vec<Rgba> = boxes
+        .iter()
+        .map(|box_| {
+            let n = box_.indices.len() as f32;
+            let mean = box_.indices.iter().fold([0.; 3], |acc, &i| {
+                let lab = points[i];
+                [acc[0] + lab[0] / n, acc[1] + lab[1] / n, acc[2] + lab[2] / n]
+            });
+            let alpha = box_.indices.iter().map(|&i| colors[i].0[3]).sum::<f32>() / n;
+            lab_to_rgba(mean, alpha)
+        })
+        .collect();
+    let palette_labs: Vec<Lab> = palette.iter().map(rgba_to_lab).collect();
+    #[allow(clippy::cast_possible_truncation)]
+    let index_map = points
+        .iter()
+        .map(|&point| {
+            palette_labs
+                .iter()
+                .enumerate()
+                .min_by(|(_, &a), (_, &b)| {
+                    lab_dist2(point, a)
+                        .partial_cmp(&lab_dist2(point, b))
+                        .expect("lab distance is never NaN")
+                })
+                .map_or(0, |(i, _)| i as u8)
+        })
+        .collect();
+    (palette, index_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use teardown_bin_format::Rgba;
+
+    use super::quantize_colors;
+
+    fn gradient(n: usize) -> Vec<Rgba> {
+        #[allow(clippy::cast_precision_loss)]
+        (0..n)
+            .map(|i| {
+                let t = i as f32 / (n - 1) as f32;
+                Rgba([t, t, t, 1.0])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn never_exceeds_256_colors() {
+        let (palette, index_map) = quantize_colors(&gradient(1000));
+        assert!(palette.len() <= 256);
+        assert_eq!(index_map.len(), 1000);
+    }
+
+    #[test]
+    fn handles_fewer_colors_than_slots() {
+        let colors = gradient(10);
+        let (palette, index_map) = quantize_colors(&colors);
+        assert!(palette.len() <= 10);
+        assert_eq!(index_map.len(), 10);
+    }
+
+    #[test]
+    fn smooth_gradient_has_low_step_error_vs_naive_rounding() {
+        let colors = gradient(1000);
+        let (palette, index_map) = quantize_colors(&colors);
+        let quantized_sum_of_squared_steps: f32 = index_map
+            .windows(2)
+            .map(|w| {
+                let [r, g, b, _] = palette[w[0] as usize].0;
+                let [r2, g2, b2, _] = palette[w[1] as usize].0;
+                (r - r2).powi(2) + (g - g2).powi(2) + (b - b2).powi(2)
+            })
+            .sum();
+        // Naive truncation to 256 buckets bands every ~4 input steps; the
+        // perceptual quantizer should produce noticeably less total jitter
+        // across a smooth gradient.
+        let naive_sum_of_squared_steps: f32 = colors
+            .windows(2)
+            .map(|w| {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let bucket = |c: &Rgba| (c.0[0] * 255.) as u8 / 4;
+                let diff = f32::from(bucket(&w[1]).abs_diff(bucket(&w[0])));
+                diff * diff * 3.
+            })
+            .sum();
+        assert!(quantized_sum_of_squared_steps <= naive_sum_of_squared_steps);
+    }
+}
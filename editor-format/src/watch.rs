@@ -0,0 +1,68 @@
+//! A blocking, debounced file watcher that turns [`SceneWriter`]'s export
+//! pipeline into a live build step: [`watch`] re-parses the source `.bin` on
+//! every change and re-runs `write_vox`/`xml`, relying on content-addressed
+//! hashing (see [`crate::vox`]) so only genuinely new geometry or palettes
+//! get rewritten. Mirrors the debounce/channel-bridging approach the GUI's
+//! own level watcher uses (`user-interface/src/graphical/watch.rs`), minus
+//! the `iced::Subscription` plumbing, since this is a plain blocking API.
+
+use std::{path::Path, sync::mpsc, time::Duration};
+
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
+use teardown_bin_format::{parse_file, EntityKind, Scene};
+
+use crate::{Result, SceneWriter};
+
+/// One export pass driven by [`watch`]: how many shape entities were
+/// exported, and the `object=` names of the `.vox` models that were freshly
+/// (re)written this pass, as opposed to already present from a previous one.
+#[derive(Debug, Default)]
+pub struct WatchReport {
+    pub entities_exported: usize,
+    pub objects_written: Vec<String>,
+}
+
+/// Watches `bin_path` and, on every change, re-parses it and hands the
+/// fresh [`Scene`] to `build`, which is expected to return a [`SceneWriter`]
+/// borrowing it (reusing whatever `mod_dir`/`vox_store`/etc the caller wants
+/// held constant across iterations). Each pass's outcome (or error) is
+/// reported to `on_export`. Blocks the calling thread, returning only if the
+/// watcher itself fails to start or reports an error.
+pub fn watch(
+    bin_path: &Path,
+    mut build: impl for<'a> FnMut(&'a Scene<'a>) -> Result<SceneWriter<'a>>,
+    mut on_export: impl FnMut(Result<WatchReport>),
+) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::watcher(tx, Duration::from_millis(500))?;
+    watcher.watch(bin_path, RecursiveMode::NonRecursive)?;
+    for event in rx {
+        match event {
+            DebouncedEvent::Create(_) | DebouncedEvent::Write(_) | DebouncedEvent::Rename(_, _) => {
+                on_export(export_once(bin_path, &mut build));
+            }
+            DebouncedEvent::Error(err, _) => return Err(err.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn export_once(
+    bin_path: &Path,
+    build: &mut impl FnMut(&Scene) -> Result<SceneWriter>,
+) -> Result<WatchReport> {
+    let scene = parse_file(bin_path)?;
+    let writer = build(&scene)?;
+    let vox_context = writer.write_vox()?;
+    let entities_exported = scene
+        .iter_entities()
+        .filter(|entity| matches!(entity.kind, EntityKind::Shape(_)))
+        .count();
+    let objects_written = vox_context.written_objects.clone();
+    writer.xml(vox_context)?;
+    Ok(WatchReport {
+        entities_exported,
+        objects_written,
+    })
+}
@@ -3,23 +3,17 @@
 
 use std::{
     self,
-    io::Write,
     iter,
     path::{Path, PathBuf},
 };
 
-pub(crate) use quick_xml::Result as XMLResult;
-use quick_xml::{
-    events::{BytesStart, Event},
-    Writer,
-};
 use teardown_bin_format::{
     environment::{self, Fog, Skybox, Sun},
-    Body, Entity, Environment, Exposure, Joint, JointKind, Light, LightKind, Rope, Script, Sound,
-    Transform, Vehicle, Water, Wheel,
+    Body, Entity, Exposure, Joint, JointKind, Light, LightKind, Rope, Script, Sound, Transform,
+    Vehicle, Water, Wheel,
 };
 
-use crate::{quaternion_to_euler, xml::WriteXML};
+use crate::quaternion_to_euler;
 
 pub trait ToXMLAttributes {
     fn to_xml_attrs(&self) -> Vec<(&'static str, String)>;
@@ -144,40 +138,6 @@ impl ToXMLAttributes for (&'static str, Sound<'_>) {
     }
 }
 
-impl<'a> WriteXML for Environment<'a> {
-    fn write_xml<W: Write>(&self, writer: &mut Writer<W>) -> XMLResult<()> {
-        writer.write_event(Event::Empty(
-            BytesStart::borrowed_name("environment".as_bytes()).with_attributes(
-                flatten(vec![
-                    self.skybox.to_xml_attrs(),
-                    self.exposure.to_xml_attrs(),
-                    self.fog.to_xml_attrs(),
-                    self.water.to_xml_attrs(),
-                    vec![
-                        ("name", "the".into()),
-                        ("nightlight", self.nightlight.to_string()),
-                        (
-                            "ambience",
-                            join_as_strings(
-                                [
-                                    self.ambience.path,
-                                    self.ambience.volume.to_string().as_ref(),
-                                ]
-                                .iter(),
-                            ),
-                        ),
-                        ("slippery", self.slippery.to_string()),
-                    ],
-                    self.fog.to_xml_attrs(),
-                ])
-                .iter()
-                .map(|(k, v)| (*k, v.as_ref())),
-            ),
-        ))?;
-        Ok(())
-    }
-}
-
 impl ToXMLAttributes for Transform {
     fn to_xml_attrs(&self) -> Vec<(&'static str, String)> {
         vec![
@@ -197,13 +157,13 @@ impl ToXMLAttributes for Light<'_> {
                     LightKind::Capsule => "capsule",
                     LightKind::Cone => "cone",
                     LightKind::Area => "area",
+                    // No "unknown" light type exists in the XML schema;
+                    // falling back to "sphere" keeps the export loadable.
+                    LightKind::Unknown(_) => "sphere",
                 }
                 .to_string(),
             ),
-            (
-                "color",
-                join_as_strings(self.rgba.0.iter().map(|c| c.powf(0.45_45_45)).take(3)),
-            ),
+            ("color", join_as_strings(self.rgba.0.iter().take(3))),
             ("scale", self.scale.to_string()),
             (
                 "angle",
@@ -302,6 +262,9 @@ impl ToXMLAttributes for Joint {
                         JointKind::Hinge => "hinge",
                         JointKind::Prismatic => "prismatic",
                         JointKind::Rope => unreachable!(),
+                        // No "unknown" joint type exists in the XML schema;
+                        // falling back to "ball" keeps the export loadable.
+                        JointKind::Unknown(_) => "ball",
                     }
                     .to_string(),
                 ),
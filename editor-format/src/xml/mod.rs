@@ -6,12 +6,14 @@ use quick_xml::{
     Writer,
 };
 use teardown_bin_format::{
-    BoundaryVertex, Entity, EntityKind, Joint, JointKind, Shape, Tags, Transform,
+    BoundaryVertex, Diagnostic, Entity, Joint, JointKind, Severity, Shape, Tags, Transform,
 };
 
 use crate::{
-    hash, quaternion_to_euler, rot_matrix_to_euler,
-    vox::{self, transform_shape, VoxelsPart},
+    color::{recolor, recolor_environment},
+    doc::{SceneNode, Value},
+    quaternion_to_euler, rot_matrix_to_euler,
+    vox::{self, transform_shape, ShapeArena, VoxelsPart},
     xml::attrs::{flatten, join_as_strings, ToXMLAttributes},
     Result, SceneWriter, WriteEntityContext, XMLResult,
 };
@@ -33,8 +35,85 @@ impl WriteXML for &[BoundaryVertex] {
     }
 }
 
+/// Builds the `<vertex pos=".."/>` children a `water` entity needs, mirroring
+/// the top-level scene boundary's [`WriteXML`] impl but as [`SceneNode`]s.
+pub(crate) fn boundary_vertex_nodes(vertices: &[BoundaryVertex]) -> Vec<SceneNode> {
+    vertices
+        .iter()
+        .map(|BoundaryVertex { x, z }| {
+            let mut node = SceneNode::new("vertex");
+            node.extend_attrs(vec![("pos", join_as_strings([x, z].iter()))]);
+            node
+        })
+        .collect()
+}
+
+/// Builds the `<vox pos=".." rot=".." file=".." object=".."/>` child of a
+/// `compound` entity for one part of its split voxel data, interning that
+/// part through `arena` like any other shape.
+pub(crate) fn compound_child_node(
+    arena: &ShapeArena<'_, '_>,
+    palette: u32,
+    voxels_part: &VoxelsPart,
+) -> Option<SceneNode> {
+    let shape_ref = arena.intern(palette, &voxels_part.voxels)?;
+    let mut transform_attrs = transform_shape(
+        &Transform {
+            pos: voxels_part.relative_pos.map(|x| x as f32 * 0.1),
+            rot: [0., 0., 0., 1.],
+        },
+        voxels_part.voxels.size,
+    )
+    .to_xml_attrs();
+    let pos_attr_value = transform_attrs.remove(0).1;
+    let rot_attr_value = transform_attrs.remove(0).1;
+    let mut node = SceneNode::new("vox");
+    node.extend_attrs(vec![
+        ("pos", pos_attr_value),
+        ("rot", rot_attr_value),
+        ("file", shape_ref.file),
+        ("object", shape_ref.object),
+    ]);
+    Some(node)
+}
+
+/// Serializes a [`SceneNode`] tree as XML: a childless node becomes a
+/// self-closing element, otherwise its children are nested inside a
+/// start/end pair.
+pub(crate) fn write_node_xml<W: Write>(node: &SceneNode, writer: &mut Writer<W>) -> XMLResult<()> {
+    let attrs = node
+        .attrs
+        .iter()
+        .map(|(key, value)| (*key, value_to_xml_attr(value)))
+        .collect::<Vec<_>>();
+    let start = BytesStart::owned_name(node.tag).with_attributes(attrs.iter().map(|(k, v)| (*k, v.as_str())));
+    if node.children.is_empty() {
+        writer.write_event(Event::Empty(start))?;
+    } else {
+        let end = start.to_end().into_owned();
+        writer.write_event(Event::Start(start))?;
+        for child in &node.children {
+            write_node_xml(child, writer)?;
+        }
+        writer.write_event(Event::End(end))?;
+    }
+    Ok(())
+}
+
+fn value_to_xml_attr(value: &Value) -> String {
+    match value {
+        Value::Str(s) => s.clone(),
+        Value::Float(f) => f.to_string(),
+        Value::Floats(floats) => join_as_strings(floats.iter()),
+    }
+}
+
 impl SceneWriter<'_> {
-    pub(crate) fn xml(&self, vox_context: vox::Context) -> Result<()> {
+    /// Writes `{name}.xml`, returning the [`Diagnostic`]s raised while
+    /// building its entity tree (see [`WriteEntityContext::diagnostics`])
+    /// so a caller can report e.g. "scene exported with 14 warnings, 3
+    /// shapes had no voxels" instead of grepping stderr.
+    pub(crate) fn xml(&self, vox_context: vox::Context) -> Result<Vec<Diagnostic>> {
         let mut xml_file = File::create(self.mod_dir.join(format!("{}.xml", &self.name)))?;
         let mut xml_writer = Writer::new(&mut xml_file);
         #[rustfmt::skip]
@@ -59,7 +138,7 @@ impl SceneWriter<'_> {
                     self.scene.player.transform.to_xml_attrs(),
                     vec![("name", "player".to_string())]
                 ]).iter().map(|(k, v)| (*k, v.as_ref())),),))?;
-        self.scene.environment.write_xml(&mut xml_writer)?;
+        self.write_environment(&mut xml_writer)?;
         Self::write_boundary(&self.scene.boundary_vertices, &mut xml_writer)?;
         xml_writer.write_event(Event::Empty(
             BytesStart::owned_name("script").with_attributes(vec![
@@ -69,15 +148,51 @@ impl SceneWriter<'_> {
             ]),
         ))?;
         let entities = self.scene.entities.iter().collect::<Vec<_>>();
-        let mut write_entity_context = WriteEntityContext {
-            vox: vox_context,
-            scene: &self.scene,
-            writer: &mut xml_writer,
-        };
+        let mut write_entity_context =
+            WriteEntityContext::new(vox_context, &self.scene, self.color_policy);
         for entity in entities {
-            write_entity_context.write_entity_xml(entity, None, false, false)?;
+            let node = write_entity_context.build_entity_node(entity, None, false, false)?;
+            write_node_xml(&node, &mut xml_writer)?;
         }
         xml_writer.write_event(Event::End(end))?;
+        Ok(write_entity_context.diagnostics)
+    }
+
+    /// Writes the single `<environment .../>` element. If
+    /// [`self.environment_color_policy`](SceneWriter::environment_color_policy)
+    /// is set, its `fogColor`/`sunColorTint`/`skyboxtint` attributes are
+    /// gamma-corrected through it first; by default they're written raw, as
+    /// they always were. This is scene-level rather than per-entity, so it
+    /// bypasses the [`SceneNode`] tree
+    /// [`WriteEntityContext::build_entity_node`](crate::WriteEntityContext::build_entity_node)
+    /// builds and writes quick_xml events directly, as it always has.
+    fn write_environment(&self, writer: &mut Writer<&mut File>) -> XMLResult<()> {
+        let environment = &self.scene.environment;
+        let attrs = flatten(vec![
+            environment.skybox.to_xml_attrs(),
+            environment.exposure.to_xml_attrs(),
+            environment.fog.to_xml_attrs(),
+            environment.water.to_xml_attrs(),
+            vec![
+                ("name", "the".into()),
+                ("nightlight", environment.nightlight.to_string()),
+                (
+                    "ambience",
+                    join_as_strings(
+                        [environment.ambience.path, environment.ambience.volume.to_string().as_ref()].iter(),
+                    ),
+                ),
+                ("slippery", environment.slippery.to_string()),
+            ],
+        ]);
+        let attrs = match self.environment_color_policy {
+            Some(policy) => recolor_environment(attrs, policy),
+            None => attrs,
+        };
+        writer.write_event(Event::Empty(
+            BytesStart::owned_name("environment")
+                .with_attributes(attrs.iter().map(|(k, v)| (*k, v.as_ref()))),
+        ))?;
         Ok(())
     }
 
@@ -94,9 +209,9 @@ impl SceneWriter<'_> {
     }
 }
 
-impl WriteEntityContext<'_, &mut File> {
+impl WriteEntityContext<'_> {
     pub(crate) fn get_shape_name_and_xml_attrs(
-        &self,
+        &mut self,
         entity: &Entity,
         shape: &Shape,
     ) -> (&'static str, Vec<(&'static str, String)>) {
@@ -114,77 +229,51 @@ impl WriteEntityContext<'_, &mut File> {
             kind_attrs.push(("scale", (shape.voxel_scaling * 10.0).to_string()))
         }
         if shape.voxels.palette_index_runs.is_empty() {
-            kind_attrs.push(("hidden_", true.to_string()))
+            kind_attrs.push(("hidden_", true.to_string()));
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                entity_handle: Some(entity.handle),
+                message: "shape has no voxels".into(),
+            });
         }
         let mut compound = false;
         if let Some(voxels_parts) = self.vox.shape_voxels_parts.get(&entity.handle) {
             if voxels_parts.len() == 1 {
-                if let Some(palette_mapping) = self.vox.palette_mappings.get(shape.palette as usize)
-                {
-                    kind_attrs.push((
-                        "file",
-                        format!(
-                            "hash/{}.vox",
-                            hash::n_to_str(hash::compute_n(palette_mapping.materials_as_ref()))
-                        ),
-                    ))
+                if let Some(shape_ref) = self.vox.shape_arena().intern(shape.palette, &voxels_parts[0].voxels) {
+                    kind_attrs.push(("file", shape_ref.file));
+                    kind_attrs.push(("object", shape_ref.object));
                 } else {
-                    eprintln!("could not get palette mapping for {}", shape.palette);
+                    self.diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        entity_handle: Some(entity.handle),
+                        message: format!("could not get palette mapping for palette {}", shape.palette),
+                    });
                 }
-                kind_attrs.push((
-                    "object",
-                    hash::n_to_str(hash::compute_n(&voxels_parts[0].voxels)),
-                ));
             } else {
                 compound = true;
             }
         } else {
-            eprintln!("could not get entity voxels for {}", entity.handle)
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                entity_handle: Some(entity.handle),
+                message: "could not get entity voxels".into(),
+            });
         }
         (if compound { "compound" } else { "vox" }, kind_attrs)
     }
 
-    pub(crate) fn write_compound_child(
-        writer: &mut Writer<&mut File>,
-        voxels_part: &VoxelsPart,
-        file_attr: (&str, &str),
-    ) -> XMLResult<()> {
-        let mut transform_attrs = transform_shape(
-            &Transform {
-                pos: voxels_part.relative_pos.map(|x| x as f32 * 0.1),
-                rot: [0., 0., 0., 1.],
-            },
-            voxels_part.voxels.size,
-        )
-        .to_xml_attrs();
-        let pos_attr_value = transform_attrs.remove(0).1;
-        let rot_attr_value = transform_attrs.remove(0).1;
-        let start = BytesStart::owned_name("vox");
-        writer.write_event(&Event::Start(start.clone().with_attributes(vec![
-            ("pos", pos_attr_value.as_str()),
-            ("rot", rot_attr_value.as_str()),
-            file_attr,
-            (
-                "object",
-                &hash::n_to_str(hash::compute_n(&voxels_part.voxels)),
-            ),
-        ])))?;
-        writer.write_event(&Event::End(start.to_end()))?;
-        Ok(())
-    }
-
-    pub(crate) fn joint_xml(&self, joint: &Joint) -> (&'static str, Vec<(&'static str, String)>) {
+    pub(crate) fn joint_xml(
+        &mut self,
+        entity: &Entity,
+        joint: &Joint,
+    ) -> (&'static str, Vec<(&'static str, String)>) {
         if joint.kind == JointKind::Rope {
             ("rope", joint.to_xml_attrs())
         } else {
             let shape_handle = joint.shape_handles[0];
             let relative_pos = joint.shape_positions[0];
             let mut attrs = joint.to_xml_attrs();
-            // FIXME: Inefficient
-            if let Some(shape) = self.scene.iter_entities().find(|e| {
-                matches!(e.kind, EntityKind::Body(_))
-                    && e.children.iter().any(|child| child.handle == shape_handle)
-            }) {
+            if let Some(&shape) = self.shape_handle_bodies.get(&shape_handle) {
                 #[allow(clippy::unwrap_used)]
                 let isometry: Isometry3<f32> = shape.transform().unwrap().clone().into();
                 let pos = isometry.transform_point(&Point3::new(
@@ -208,6 +297,11 @@ impl WriteEntityContext<'_, &mut File> {
                         .into(),
                     );
                     rot.renormalize();
+                    self.diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        entity_handle: Some(entity.handle),
+                        message: "joint rotation was renormalized".into(),
+                    });
                     attrs.push(("rot", join_as_strings(rot_matrix_to_euler(rot).iter())));
                 }
             }
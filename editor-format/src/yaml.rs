@@ -0,0 +1,94 @@
+//! A YAML dump of the same per-entity [`SceneNode`] tree the XML writer in
+//! [`crate::xml`] consumes, so two exports of the same map can be diffed
+//! line-by-line (each attribute gets its own line, unlike XML where they're
+//! all packed onto one element's opening tag).
+
+use std::{fs::File, io::Write};
+
+use crate::{
+    doc::{SceneNode, Value},
+    Result, SceneWriter, WriteEntityContext,
+};
+
+fn push_indent(out: &mut String, indent: usize) {
+    for _ in 0..indent {
+        out.push_str("  ");
+    }
+}
+
+/// Quotes `s` if it's empty or contains `:`, `#`, a newline or a quote
+/// character. Other strings are written bare, including ones that YAML would
+/// read as a number or a boolean or that contain other YAML indicators.
+fn scalar(s: &str) -> String {
+    if s.is_empty() || s.chars().any(|c| matches!(c, ':' | '#' | '\n' | '"' | '\'')) {
+        format!("{:?}", s)
+    } else {
+        s.to_string()
+    }
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Str(s) => out.push_str(&scalar(s)),
+        Value::Float(f) => out.push_str(&f.to_string()),
+        Value::Floats(floats) => {
+            out.push('[');
+            for (i, f) in floats.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                out.push_str(&f.to_string());
+            }
+            out.push(']');
+        }
+    }
+}
+
+fn write_node(out: &mut String, node: &SceneNode, indent: usize) {
+    push_indent(out, indent);
+    out.push_str("- tag: ");
+    out.push_str(node.tag);
+    out.push('\n');
+    if !node.attrs.is_empty() {
+        push_indent(out, indent + 1);
+        out.push_str("attrs:\n");
+        for (key, value) in &node.attrs {
+            push_indent(out, indent + 2);
+            out.push_str(key);
+            out.push_str(": ");
+            write_value(out, value);
+            out.push('\n');
+        }
+    }
+    if let Some(body) = &node.body {
+        push_indent(out, indent + 1);
+        out.push_str("body: ");
+        out.push_str(&scalar(body));
+        out.push('\n');
+    }
+    if !node.children.is_empty() {
+        push_indent(out, indent + 1);
+        out.push_str("children:\n");
+        for child in &node.children {
+            write_node(out, child, indent + 2);
+        }
+    }
+}
+
+impl SceneWriter<'_> {
+    /// Writes `{name}.yaml`: the same entity tree [`SceneWriter::write_scene`]
+    /// emits as XML, as indented, line-stable YAML instead.
+    pub fn write_yaml(&self) -> Result<()> {
+        let vox_context = self.write_vox()?;
+        let mut write_entity_context =
+            WriteEntityContext::new(vox_context, &self.scene, self.color_policy);
+        let mut out = String::from("entities:\n");
+        for entity in &self.scene.entities {
+            let node = write_entity_context.build_entity_node(entity, None, false, false)?;
+            write_node(&mut out, &node, 1);
+        }
+        let mut file = File::create(self.mod_dir.join(format!("{}.yaml", &self.name)))?;
+        file.write_all(out.as_bytes())?;
+        Ok(())
+    }
+}
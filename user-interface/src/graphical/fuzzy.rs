@@ -0,0 +1,31 @@
+//! A lightweight subsequence "flex" matcher for [`MainView`](super::MainView)'s
+//! level filter box: scores how well `query`'s characters appear, in order,
+//! somewhere in `candidate`, rewarding contiguous runs and word-boundary
+//! starts the way fuzzy-finders like fzf do. `None` means `query` isn't a
+//! subsequence of `candidate` at all.
+
+const CONTIGUOUS_BONUS: i32 = 5;
+const WORD_BOUNDARY_BONUS: i32 = 3;
+
+pub(crate) fn fuzzy_score(candidate: &str, query: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut search_from = 0;
+    let mut last_match: Option<usize> = None;
+    for query_char in query.to_lowercase().chars() {
+        let matched = search_from + candidate[search_from..].iter().position(|&c| c == query_char)?;
+        score += 1;
+        if last_match == Some(matched.wrapping_sub(1)) {
+            score += CONTIGUOUS_BONUS;
+        }
+        if matched == 0 || !candidate[matched - 1].is_alphanumeric() {
+            score += WORD_BOUNDARY_BONUS;
+        }
+        last_match = Some(matched);
+        search_from = matched + 1;
+    }
+    Some(score)
+}
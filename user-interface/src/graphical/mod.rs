@@ -1,22 +1,28 @@
 #![allow(clippy::default_trait_access)] // Default UI state is irrelevant
 mod alphanum_ord;
+mod fuzzy;
+mod preview;
 mod style;
+mod watch;
 
 use std::{
+    collections::VecDeque,
     fmt::{self, Debug, Formatter},
     fs::{self, ReadDir}, mem,
     path::{PathBuf, Path},
     sync::{Arc, Mutex}, backtrace::BacktraceStatus,
+    time::Instant,
 };
 
 use anyhow::{Result, Context};
 use iced::{
     button, executor, scrollable, Align, Application, Button, Clipboard, Column, Command, Element,
-    Length, Row, Rule, Scrollable, Space, Text, VerticalAlignment, TextInput, text_input,
+    Image, Length, Row, Rule, Scrollable, Space, Subscription, Text, VerticalAlignment, TextInput,
+    text_input,
 };
 use lazy_static::lazy_static;
 use regex::Regex;
-use teardown_bin_format::{parse_file, OwnedScene};
+use teardown_bin_format::{parse_file, EntityKind, OwnedScene};
 use teardown_editor_format::{util::UnwrapLock, vox, SceneWriterBuilder};
 
 use self::alphanum_ord::AlphanumericOrd;
@@ -28,8 +34,19 @@ pub struct MainView {
     n_special_levels: usize,
     selected_level: Option<usize>,
     vox_store: Arc<Mutex<vox::Store>>,
+    theme: Arc<style::ThemeConfig>,
     button_help: button::State,
+    button_convert_all: button::State,
     scroll_state: scrollable::State,
+    filter_query: String,
+    filter_input: text_input::State,
+    convert_all: Option<ConvertAllState>,
+}
+
+struct ConvertAllState {
+    queue: VecDeque<PathBuf>,
+    total: usize,
+    done: usize,
 }
 
 enum Load<T> {
@@ -41,10 +58,56 @@ enum Load<T> {
 struct Level {
     path: PathBuf,
     name: String,
-    scene: Load<OwnedScene>,
+    scene: Load<Arc<OwnedScene>>,
     button_select: button::State,
     button_to_xml: button::State,
-    button_to_blender: button::State
+    button_to_blender: button::State,
+    button_select_animation: style::LevelButtonAnimation,
+    preview: Option<PreviewState>,
+    thumbnail: Option<iced::image::Handle>,
+    theme: Arc<style::ThemeConfig>,
+}
+
+struct PreviewState {
+    camera: preview::OrbitCamera,
+    grid: preview::VoxelGrid,
+    image: iced::image::Handle,
+    button_orbit_left: button::State,
+    button_orbit_right: button::State,
+    button_orbit_up: button::State,
+    button_orbit_down: button::State,
+    button_zoom_in: button::State,
+    button_zoom_out: button::State,
+}
+
+impl PreviewState {
+    /// Builds a preview from the first `Shape` entity found in `scene`, if
+    /// any (most levels have at least one).
+    fn from_scene(scene: &OwnedScene) -> Option<Self> {
+        let shape = scene.iter_entities().find_map(|entity| match &entity.kind {
+            EntityKind::Shape(shape) => Some(shape),
+            _ => None,
+        })?;
+        let materials = &scene.palettes.get(shape.palette as usize)?.materials;
+        let grid = preview::VoxelGrid::new(&shape.voxels, materials);
+        let camera = preview::OrbitCamera::framing(shape.voxels.size);
+        let image = preview::render_handle(&grid, &camera);
+        Some(Self {
+            camera,
+            grid,
+            image,
+            button_orbit_left: Default::default(),
+            button_orbit_right: Default::default(),
+            button_orbit_up: Default::default(),
+            button_orbit_down: Default::default(),
+            button_zoom_in: Default::default(),
+            button_zoom_out: Default::default(),
+        })
+    }
+
+    fn re_render(&mut self) {
+        self.image = preview::render_handle(&self.grid, &self.camera);
+    }
 }
 
 fn level_path_to_id(path: &Path) -> String {
@@ -71,13 +134,25 @@ fn write_scene_and_vox(
     Ok(())
 }
 
+fn write_blender_mesh(scene_writer_builder: &SceneWriterBuilder) -> Result<()> {
+    scene_writer_builder
+        .build()
+        .map_err(Error::SceneWriterBuild)?
+        .write_blender_mesh()?;
+    Ok(())
+}
+
 impl Level {
-    fn new(path: PathBuf, name: String) -> Self {
+    fn new(path: PathBuf, name: String, theme: Arc<style::ThemeConfig>) -> Self {
         Self {
             path, name, scene: Load::None,
             button_select: Default::default(),
             button_to_xml: Default::default(),
             button_to_blender: Default::default(),
+            button_select_animation: style::LevelButtonAnimation::new(&theme, false, false),
+            preview: None,
+            thumbnail: None,
+            theme,
         }
     }
 
@@ -92,11 +167,42 @@ impl Level {
                     ]).padding(5).into()
                 }
                 Load::Loaded(scene) => {
+                    let thumbnail_view = self.thumbnail.as_ref().map(|handle| {
+                        #[allow(clippy::cast_possible_truncation)]
+                        Image::new(handle.clone())
+                            .width(Length::Units(preview::THUMBNAIL_SIZE.0 as u16))
+                            .height(Length::Units(preview::THUMBNAIL_SIZE.1 as u16))
+                    });
+                    let preview_view = self.preview.as_mut().map(|preview| {
+                        Column::with_children(vec![
+                            #[allow(clippy::cast_possible_truncation)]
+                            Image::new(preview.image.clone())
+                                .width(Length::Units(preview::PREVIEW_SIZE.0 as u16))
+                                .height(Length::Units(preview::PREVIEW_SIZE.1 as u16))
+                                .into(),
+                            Row::with_children(vec![
+                                Button::new(&mut preview.button_orbit_left, Text::new("<"))
+                                    .on_press(LevelMessage::PreviewOrbit(-0.3, 0.)).into(),
+                                Button::new(&mut preview.button_orbit_up, Text::new("^"))
+                                    .on_press(LevelMessage::PreviewOrbit(0., 0.3)).into(),
+                                Button::new(&mut preview.button_orbit_down, Text::new("v"))
+                                    .on_press(LevelMessage::PreviewOrbit(0., -0.3)).into(),
+                                Button::new(&mut preview.button_orbit_right, Text::new(">"))
+                                    .on_press(LevelMessage::PreviewOrbit(0.3, 0.)).into(),
+                                Button::new(&mut preview.button_zoom_in, Text::new("+"))
+                                    .on_press(LevelMessage::PreviewZoom(0.8)).into(),
+                                Button::new(&mut preview.button_zoom_out, Text::new("-"))
+                                    .on_press(LevelMessage::PreviewZoom(1.25)).into(),
+                            ]).into(),
+                        ])
+                    });
                     Column::with_children(vec![
+                        thumbnail_view.map_or_else(|| Space::with_height(Length::Units(0)).into(), Into::into),
                         Column::with_children(vec![
                             Text::new(scene.level).into(),
                             Text::new(format!("Entities: {}", scene.iter_entities().count())).into(),
                         ]).padding(5).into(),
+                        preview_view.map_or_else(|| Space::with_height(Length::Units(0)).into(), Into::into),
                         Space::with_height(Length::Fill).into(),
                         Row::with_children(vec![
                             Text::new("Convert to ...".to_string())
@@ -107,18 +213,22 @@ impl Level {
                                 .on_press(LevelMessage::ConvertXML).into(),
                             // Space::with_width(Length::Fill).into(),
                             Button::new(&mut self.button_to_blender, Text::new("Blender"))
-                            .width(Length::Fill).into()
+                            .width(Length::Fill)
+                            .on_press(LevelMessage::ConvertBlender).into()
                         ]).align_items(Align::Center).padding(15).into()
                     ]).into()
                 }
             })
         } else { None };
         let button = {
-            let text = Text::new(self.name.clone());
+            let text = Text::new(self.name.clone()).size(self.theme.font_size);
             let mut button = Button::new(&mut self.button_select, Row::with_children(vec![text.into(), Space::with_width(Length::Fill).into()]));
-            button = button.style(style::LevelButton {
-                selected: selected || matches!(self.scene, Load::Loading),
-                loaded: matches!(self.scene, Load::Loaded(_)) });
+            self.button_select_animation.set_target(
+                &self.theme,
+                selected || matches!(self.scene, Load::Loading),
+                matches!(self.scene, Load::Loaded(_)));
+            let style = self.button_select_animation.advance(Instant::now());
+            button = button.style(style::AnimatedButton(style));
             button
         };
         LevelViews { button, side }
@@ -145,7 +255,28 @@ impl Level {
                             write_scene_and_vox(&builder, &vox_store).map(|_| scene)
                         },
                         |scene_result| match scene_result {
-                            Ok(scene) => LevelMessage::XMLConverted(Arc::new(scene)),
+                            Ok(scene) => LevelMessage::XMLConverted(scene),
+                            Err(err) => LevelMessage::Error(Arc::new(err)),
+                        },
+                    );
+                }
+                other => self.scene = other,
+            },
+            LevelMessage::ConvertBlender => match mem::replace(&mut self.scene, Load::Loading) {
+                Load::Loaded(scene) => {
+                    let dirs = dirs.clone();
+                    let vox_store = vox_store.clone();
+                    return Command::perform(
+                        async move {
+                            let mut builder = SceneWriterBuilder::default();
+                            builder
+                                .vox_store(vox_store)
+                                .mod_dir(dirs.mods.join("converted"))
+                                .scene(&scene);
+                            write_blender_mesh(&builder).map(|_| scene)
+                        },
+                        |scene_result| match scene_result {
+                            Ok(scene) => LevelMessage::BlenderConverted(scene),
                             Err(err) => LevelMessage::Error(Arc::new(err)),
                         },
                     );
@@ -153,31 +284,50 @@ impl Level {
                 other => self.scene = other,
             },
             LevelMessage::SceneLoaded(scene) => {
-                self.scene = Load::Loaded(if let Ok(scene_result) = Arc::try_unwrap(scene) {
-                    match scene_result {
-                        Ok(scene) => scene,
-                        Err(error) => {
-                            // Let this be caught by Main
-                            return Command::perform(
-                                async move { LevelMessage::Error(Arc::new(anyhow::Error::msg(error))) },
-                                |level_message| level_message,
-                            )
-                        }
+                let scene = match Arc::try_unwrap(scene) {
+                    Ok(Ok(scene)) => Arc::new(scene),
+                    Ok(Err(error)) => {
+                        // Let this be caught by Main
+                        return Command::perform(
+                            async move { LevelMessage::Error(Arc::new(anyhow::Error::msg(error))) },
+                            |level_message| level_message,
+                        )
                     }
-                } else {
-                    panic!("Arc::try_unwrap")
-                });
+                    Err(_) => panic!("Arc::try_unwrap"),
+                };
+                self.preview = PreviewState::from_scene(&scene);
+                self.scene = Load::Loaded(scene.clone());
+                return Command::perform(
+                    async move { preview::render_scene_top_down_handle(&scene) },
+                    LevelMessage::ThumbnailRendered,
+                );
             }
             LevelMessage::XMLConverted(scene) => {
-                self.scene = Load::Loaded(if let Ok(ok) = Arc::try_unwrap(scene) {
-                    ok
-                } else {
-                    panic!("Arc::try_unwrap")
-                });
+                self.preview = PreviewState::from_scene(&scene);
+                self.scene = Load::Loaded(scene);
+            }
+            LevelMessage::BlenderConverted(scene) => {
+                self.preview = PreviewState::from_scene(&scene);
+                self.scene = Load::Loaded(scene);
+            }
+            LevelMessage::ThumbnailRendered(handle) => {
+                self.thumbnail = Some(handle);
             }
             LevelMessage::Error(error) => {
                 panic!("{:?}", error);
             }
+            LevelMessage::PreviewOrbit(dyaw, dpitch) => {
+                if let Some(preview) = &mut self.preview {
+                    preview.camera.orbit(dyaw, dpitch);
+                    preview.re_render();
+                }
+            }
+            LevelMessage::PreviewZoom(factor) => {
+                if let Some(preview) = &mut self.preview {
+                    preview.camera.zoom(factor);
+                    preview.re_render();
+                }
+            }
         }
 
         Command::none()
@@ -201,15 +351,24 @@ impl Level {
 #[derive(Clone)]
 pub enum LevelMessage {
     ConvertXML,
+    ConvertBlender,
     SceneLoaded(Arc<Result<OwnedScene>>),
     XMLConverted(Arc<OwnedScene>),
+    BlenderConverted(Arc<OwnedScene>),
+    ThumbnailRendered(iced::image::Handle),
     Error(Arc<anyhow::Error>),
+    PreviewOrbit(f32, f32),
+    PreviewZoom(f32),
 }
 
 #[derive(Clone)]
 pub enum MainMessage {
     Level(usize, LevelMessage),
     SelectLevel(usize),
+    Filter(String),
+    FileChanged(PathBuf),
+    ConvertAll,
+    ConvertAllProgress(Result<(), Arc<anyhow::Error>>),
     Help,
     HelpQuit,
     Error(Arc<anyhow::Error>),
@@ -250,31 +409,41 @@ fn read_dir_with_ctx<P: AsRef<Path>>(path: P) -> Result<ReadDir> {
     fs::read_dir(path.as_ref()).context(format!("Reading directory \"{}\"", path.as_ref().display()))
 }
 
-fn init_levels(dirs: &Directories, lua_game_meta: GameLuaMeta) -> Result<Vec<Level>> {
+fn init_levels(
+    dirs: &Directories,
+    lua_game_meta: GameLuaMeta,
+    theme: &Arc<style::ThemeConfig>,
+) -> Result<Vec<Level>> {
     let mut levels = read_dir_with_ctx(dirs.main.join("data").join("bin"))?
         .map(|res| res.map(|dir_entry| {
             let path = dir_entry.path();
             let id = level_path_to_id(&path);
-            Level::new(path, name_from_level_id(&id, &lua_game_meta).unwrap_or(id))
+            Level::new(path, name_from_level_id(&id, &lua_game_meta).unwrap_or(id), theme.clone())
         }))
         .collect::<Result<Vec<_>, _>>()?;
     levels.sort_by_cached_key(|x| AlphanumericOrd(x.name.clone()));
-    levels.insert(0, Level::new(dirs.progress.join("quicksave.bin"), "Last quicksave".to_string()));
+    levels.insert(0, Level::new(dirs.progress.join("quicksave.bin"), "Last quicksave".to_string(), theme.clone()));
     Ok(levels)
 }
 
 impl MainView {
     fn new(dirs: Directories) -> Result<Self> {
-        let levels = init_levels(&dirs, load_level_meta()?)?;
+        let theme = Arc::new(style::load_theme());
+        let levels = init_levels(&dirs, load_level_meta()?, &theme)?;
         let vox_store = vox::Store::new(&dirs.main)?;
         Ok(MainView {
             levels,
             n_special_levels: 1,
             selected_level: None,
             vox_store,
+            theme,
             dirs,
             button_help: Default::default(),
+            button_convert_all: Default::default(),
             scroll_state: Default::default(),
+            filter_query: String::new(),
+            filter_input: Default::default(),
+            convert_all: None,
         })
     }
 
@@ -319,22 +488,137 @@ impl MainView {
                 },
             ),
             MainMessage::HelpQuit => Command::none(),
+            MainMessage::Filter(query) => {
+                self.filter_query = query;
+                Command::none()
+            }
+            MainMessage::FileChanged(path) => self.on_file_changed(path),
+            MainMessage::ConvertAll => {
+                let queue: VecDeque<PathBuf> =
+                    self.levels[self.n_special_levels..].iter().map(|level| level.path.clone()).collect();
+                self.convert_all = Some(ConvertAllState { total: queue.len(), queue, done: 0 });
+                self.advance_convert_all()
+            }
+            MainMessage::ConvertAllProgress(result) => {
+                if let Err(err) = result {
+                    eprintln!("Batch convert: could not convert a level: {:#}", err);
+                }
+                if let Some(state) = &mut self.convert_all {
+                    state.done += 1;
+                }
+                self.advance_convert_all()
+            }
             MainMessage::Error(_) => unreachable!("caught by App"),
         }
     }
 
+    /// Converts the next queued level (if any), or, once the queue is
+    /// empty, flushes the shared [`vox::Store`]'s dirty palettes once and
+    /// ends the batch.
+    fn advance_convert_all(&mut self) -> Command<MainMessage> {
+        let path = match &mut self.convert_all {
+            Some(state) => state.queue.pop_front(),
+            None => None,
+        };
+        match path {
+            Some(path) => {
+                let dirs = self.dirs.clone();
+                let vox_store = self.vox_store.clone();
+                Command::perform(
+                    async move {
+                        let scene = parse_file(&path)?;
+                        let mut builder = SceneWriterBuilder::default();
+                        builder
+                            .vox_store(vox_store)
+                            .mod_dir(dirs.mods.join(level_path_to_id(&path)))
+                            .scene(&scene);
+                        builder.build().map_err(Error::SceneWriterBuild)?.write_scene()?;
+                        Ok(())
+                    },
+                    |result: Result<()>| MainMessage::ConvertAllProgress(result.map_err(Arc::new)),
+                )
+            }
+            None => {
+                if self.convert_all.take().is_some() {
+                    if let Err(err) = self.vox_store.unwrap_lock().write_dirty() {
+                        eprintln!("Batch convert: could not flush the vox store: {:#}", err);
+                    }
+                }
+                Command::none()
+            }
+        }
+    }
+
+    /// A `.bin` level or quicksave under a watched directory appeared,
+    /// changed, or disappeared: invalidate the matching [`Level`]'s cached
+    /// scene (re-loading it immediately if it's the selected one), or, for a
+    /// path that isn't a known level yet, add it to the list.
+    fn on_file_changed(&mut self, path: PathBuf) -> Command<MainMessage> {
+        if let Some(index) = self.levels.iter().position(|level| level.path == path) {
+            self.levels[index].scene = Load::None;
+            self.levels[index].preview = None;
+            self.levels[index].thumbnail = None;
+            if self.selected_level == Some(index) {
+                return self
+                    .levels[index]
+                    .load_scene(true)
+                    .map(move |what| MainMessage::Level(index, what));
+            }
+        } else if path.extension().map_or(false, |ext| ext == "bin") {
+            let id = level_path_to_id(&path);
+            let level = Level::new(path, id, self.theme.clone());
+            let insert_at = self.levels[self.n_special_levels..]
+                .iter()
+                .position(|existing| AlphanumericOrd(existing.name.clone()) > AlphanumericOrd(level.name.clone()))
+                .map_or(self.levels.len(), |i| i + self.n_special_levels);
+            self.levels.insert(insert_at, level);
+        }
+        Command::none()
+    }
+
+    fn subscription(&self) -> Subscription<MainMessage> {
+        watch::watch(vec![self.dirs.main.join("data").join("bin"), self.dirs.progress.clone()])
+    }
+
+    /// Level indices to display, in order: the special levels untouched,
+    /// then the rest either in their usual [`AlphanumericOrd`] order (no
+    /// query) or ranked by [`fuzzy::fuzzy_score`] (query present).
+    fn visible_level_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.n_special_levels).collect();
+        if self.filter_query.is_empty() {
+            order.extend(self.n_special_levels..self.levels.len());
+        } else {
+            let mut matches: Vec<(usize, i32)> = self.levels.iter().enumerate()
+                .skip(self.n_special_levels)
+                .filter_map(|(i, level)| fuzzy::fuzzy_score(&level.name, &self.filter_query).map(|score| (i, score)))
+                .collect();
+            matches.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+            order.extend(matches.into_iter().map(|(i, _)| i));
+        }
+        order
+    }
+
     #[rustfmt::skip]
     fn view(&mut self) -> Element<'_, MainMessage> {
         let selected_level = self.selected_level;
-        let (level_buttons, mut level_side_views) = self.levels.iter_mut().enumerate().map(|(i, level)| {
-            let mut view = level.view(selected_level == Some(i));
+        let order = self.visible_level_order();
+        let (level_buttons, level_side_views): (Vec<_>, Vec<_>) = order.iter().map(|&i| {
+            let mut view = self.levels[i].view(selected_level == Some(i));
             view.button = view.button.on_press(MainMessage::SelectLevel(i));
-            (view.button, view.side)
-        }).unzip::<_, _, Vec<_>, Vec<_>>();
+            (view.button, (i, view.side))
+        }).unzip();
         Column::with_children(vec![
             Row::with_children(vec![
                 Text::new(format!("{} palette files cached", self.vox_store.unwrap_lock().palette_files.len())).into(),
                 Space::with_width(Length::Fill).into(),
+                if let Some(state) = &self.convert_all {
+                    Text::new(format!("Converting {}/{} ...", state.done, state.total)).into()
+                } else {
+                    Button::new(&mut self.button_convert_all, Text::new("Convert all to Editor"))
+                    .on_press(MainMessage::ConvertAll)
+                    .into()
+                },
+                Space::with_width(10.into()).into(),
                 Button::new(&mut self.button_help, Text::new("Help"))
                 .on_press(MainMessage::Help)
                 .into()
@@ -345,11 +629,13 @@ impl MainView {
                 Column::with_children({
                     let mut level_buttons_iter = level_buttons.into_iter();
                     let special_buttons = level_buttons_iter.by_ref().take(self.n_special_levels).map(Into::into).collect::<Vec<_>>();
+                    let filter_input = TextInput::new(&mut self.filter_input, "Filter levels...", &self.filter_query, MainMessage::Filter);
                     let mut scrollable = Scrollable::new(&mut self.scroll_state).style(style::Theme);
                     for button in level_buttons_iter {
                         scrollable = scrollable.push(button);
                     }
                     vec![
+                        filter_input.into(),
                         Column::with_children(special_buttons).into(),
                         Rule::horizontal(2).into(),
                         scrollable.into()
@@ -357,8 +643,8 @@ impl MainView {
                 })
                 .width(Length::FillPortion(1)).into(),
                 Column::with_children(if let Some(selected) = self.selected_level {
-                    if let Some(level_side_view) = level_side_views.remove(selected) {
-                        vec![level_side_view.map(move |level_message| MainMessage::Level(selected, level_message))]
+                    if let Some(side) = level_side_views.into_iter().find_map(|(i, side)| if i == selected { side } else { None }) {
+                        vec![side.map(move |level_message| MainMessage::Level(selected, level_message))]
                     } else {
                         vec![]
                     }
@@ -503,6 +789,13 @@ impl Application for App {
         "Parse and convert the binary format for Teardown".to_string()
     }
 
+    fn subscription(&self) -> Subscription<Self::Message> {
+        match self {
+            App::Main(main_view) => main_view.subscription().map(AppMessage::Main),
+            App::SetDirectories(_) | App::Error(_) => Subscription::none(),
+        }
+    }
+
     fn update(
         &mut self,
         message: Self::Message,
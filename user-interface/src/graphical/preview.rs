@@ -0,0 +1,390 @@
+//! Software-rendered, orbit-camera preview of a converted voxel shape.
+//!
+//! Steps a ray through the voxel grid with a DDA (Amanatides-Woo) march to
+//! find the first solid voxel and its hit face normal, then shades it with a
+//! simple ambient + diffuse + specular Phong model using the same converted
+//! `vox::semantic::Material` that export writes out, so the material math in
+//! `convert_material` can be checked visually before exporting.
+
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+use iced::image;
+use teardown_bin_format::{EntityKind, Material, OwnedScene, Palette, Shape, Voxels};
+use teardown_editor_format::vox::convert_material;
+use vox::semantic::Material as VoxMaterial;
+
+type Vec3 = [f32; 3];
+
+fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: Vec3, s: f32) -> Vec3 {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn length(a: Vec3) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn normalize(a: Vec3) -> Vec3 {
+    scale(a, 1.0 / length(a).max(1e-6))
+}
+
+/// A camera that orbits around a fixed target, driven by discrete
+/// yaw/pitch/zoom steps from UI buttons; only the zoom steps change its
+/// distance from the target.
+pub struct OrbitCamera {
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+    target: Vec3,
+}
+
+impl OrbitCamera {
+    /// Points the camera at the center of a `size`-sized voxel grid, far
+    /// enough back to frame it.
+    pub fn framing(size: [u32; 3]) -> Self {
+        let size_f = [size[0] as f32, size[1] as f32, size[2] as f32];
+        Self {
+            yaw: FRAC_PI_4,
+            pitch: 0.5,
+            distance: length(size_f).max(1.0) * 1.5,
+            target: scale(size_f, 0.5),
+        }
+    }
+
+    pub fn orbit(&mut self, dyaw: f32, dpitch: f32) {
+        self.yaw += dyaw;
+        let limit = FRAC_PI_2 - 0.01;
+        self.pitch = (self.pitch + dpitch).clamp(-limit, limit);
+    }
+
+    pub fn zoom(&mut self, factor: f32) {
+        self.distance = (self.distance * factor).max(0.5);
+    }
+
+    fn eye(&self) -> Vec3 {
+        let (sy, cy) = self.yaw.sin_cos();
+        let (sp, cp) = self.pitch.sin_cos();
+        add(self.target, scale([cy * cp, sp, sy * cp], self.distance))
+    }
+
+    fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        let forward = normalize(sub(self.target, self.eye()));
+        let right = normalize(cross(forward, [0., 1., 0.]));
+        let up = cross(right, forward);
+        (forward, right, up)
+    }
+}
+
+/// A dense voxel grid with its palette already converted to `vox::semantic`
+/// materials, ready to be marched and shaded.
+pub struct VoxelGrid {
+    size: [u32; 3],
+    indices: Vec<u8>,
+    materials: Vec<VoxMaterial>,
+}
+
+impl VoxelGrid {
+    pub fn new(voxels: &Voxels, materials: &[Material; 256]) -> Self {
+        let size = voxels.size;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let volume = size[0] as usize * size[1] as usize * size[2] as usize;
+        let mut indices = vec![0_u8; volume];
+        #[allow(clippy::cast_sign_loss)]
+        for (pos, palette_index) in voxels.iter() {
+            let [x, y, z] = [pos[0] as usize, pos[1] as usize, pos[2] as usize];
+            let i = (z * size[1] as usize + y) * size[0] as usize + x;
+            if let Some(slot) = indices.get_mut(i) {
+                *slot = palette_index;
+            }
+        }
+        let materials = materials.iter().map(convert_material).collect();
+        Self {
+            size,
+            indices,
+            materials,
+        }
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn get(&self, x: i32, y: i32, z: i32) -> u8 {
+        if x < 0
+            || y < 0
+            || z < 0
+            || x as u32 >= self.size[0]
+            || y as u32 >= self.size[1]
+            || z as u32 >= self.size[2]
+        {
+            0
+        } else {
+            let i = (z as usize * self.size[1] as usize + y as usize) * self.size[0] as usize
+                + x as usize;
+            self.indices[i]
+        }
+    }
+
+    /// Marches a ray from `origin` in direction `dir` through the grid using
+    /// an Amanatides-Woo DDA step, returning the first solid voxel's palette
+    /// index and the normal of the face the ray entered through.
+    fn march(&self, origin: Vec3, dir: Vec3) -> Option<(u8, Vec3)> {
+        let sign = |x: f32| -> i32 {
+            if x > 0. {
+                1
+            } else if x < 0. {
+                -1
+            } else {
+                0
+            }
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let mut voxel = [
+            origin[0].floor() as i32,
+            origin[1].floor() as i32,
+            origin[2].floor() as i32,
+        ];
+        let step = [sign(dir[0]), sign(dir[1]), sign(dir[2])];
+        let mut t_max = [0_f32; 3];
+        let mut t_delta = [0_f32; 3];
+        for axis in 0..3 {
+            if dir[axis].abs() < 1e-9 {
+                t_max[axis] = f32::INFINITY;
+                t_delta[axis] = f32::INFINITY;
+            } else {
+                #[allow(clippy::cast_precision_loss)]
+                let next_boundary = if dir[axis] > 0. {
+                    (voxel[axis] + 1) as f32
+                } else {
+                    voxel[axis] as f32
+                };
+                t_max[axis] = (next_boundary - origin[axis]) / dir[axis];
+                t_delta[axis] = (1.0 / dir[axis]).abs();
+            }
+        }
+        let mut normal = [0_f32; 3];
+        let max_steps = (self.size[0] + self.size[1] + self.size[2]) as usize * 2 + 4;
+        for _ in 0..max_steps {
+            let palette_index = self.get(voxel[0], voxel[1], voxel[2]);
+            if palette_index != 0 {
+                return Some((palette_index, normal));
+            }
+            let axis = if t_max[0] < t_max[1] {
+                if t_max[0] < t_max[2] { 0 } else { 2 }
+            } else if t_max[1] < t_max[2] {
+                1
+            } else {
+                2
+            };
+            voxel[axis] += step[axis];
+            t_max[axis] += t_delta[axis];
+            #[allow(clippy::cast_precision_loss)]
+            {
+                normal = [0_f32; 3];
+                normal[axis] = -step[axis] as f32;
+            }
+        }
+        None
+    }
+}
+
+const BACKGROUND: Vec3 = [0.05, 0.05, 0.08];
+const LIGHT_DIR: Vec3 = [0.3940552, 0.7881104, 0.4728662]; // normalize([0.5, 1.0, 0.6])
+
+#[allow(clippy::cast_precision_loss)]
+fn shade(material: &VoxMaterial, normal: Vec3, incoming_dir: Vec3) -> Vec3 {
+    let base = [
+        f32::from(material.rgba[0]) / 255.,
+        f32::from(material.rgba[1]) / 255.,
+        f32::from(material.rgba[2]) / 255.,
+    ];
+    if let Some(emit) = material.emit {
+        let flux = material.flux.unwrap_or(1.0);
+        let radiance = (emit * 10_f32.powf(flux - 1.0)).min(4.0);
+        return scale(base, radiance);
+    }
+    let n_dot_l = dot(normal, LIGHT_DIR).max(0.0);
+    let view_dir = scale(incoming_dir, -1.0);
+    let reflected = normalize(sub(scale(normal, 2.0 * dot(normal, LIGHT_DIR)), LIGHT_DIR));
+    let r_dot_v = dot(reflected, view_dir).max(0.0);
+    let roughness = material.rough.unwrap_or(0.5);
+    let shininess = (1.0 - roughness).powi(2).mul_add(128.0, 1.0);
+    let spec_strength = material.spec.unwrap_or(0.04);
+    let metalness = material.metal.unwrap_or(0.0);
+    // Metals tint their highlight with the base color; dielectrics stay
+    // close to white, per the standard metal/dielectric Fresnel split.
+    let spec_color = [
+        spec_strength.mul_add(1.0 - metalness, base[0] * spec_strength * metalness),
+        spec_strength.mul_add(1.0 - metalness, base[1] * spec_strength * metalness),
+        spec_strength.mul_add(1.0 - metalness, base[2] * spec_strength * metalness),
+    ];
+    let ambient = scale(base, 0.1);
+    let diffuse = scale(base, n_dot_l * (1.0 - metalness));
+    let specular = scale(spec_color, r_dot_v.powf(shininess));
+    add(add(ambient, diffuse), specular)
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn to_u8(c: f32) -> u8 {
+    (c.clamp(0., 1.) * 255.0).round() as u8
+}
+
+/// Renders `grid` as seen by `camera` into an RGBA8 buffer of `width *
+/// height * 4` bytes.
+#[allow(clippy::cast_precision_loss)]
+pub fn render(grid: &VoxelGrid, camera: &OrbitCamera, width: u32, height: u32) -> Vec<u8> {
+    let eye = camera.eye();
+    let (forward, right, up) = camera.basis();
+    let aspect = width as f32 / height.max(1) as f32;
+    let half_fov_tan = FRAC_PI_4.tan();
+    let mut pixels = vec![0_u8; (width * height * 4) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let ndc_x = ((x as f32 + 0.5) / width as f32 * 2.0 - 1.0) * aspect * half_fov_tan;
+            let ndc_y = (1.0 - (y as f32 + 0.5) / height as f32 * 2.0) * half_fov_tan;
+            let dir = normalize(add(forward, add(scale(right, ndc_x), scale(up, ndc_y))));
+            let color = grid.march(eye, dir).map_or(BACKGROUND, |(palette_index, normal)| {
+                shade(&grid.materials[palette_index as usize], normal, dir)
+            });
+            let i = ((y * width + x) * 4) as usize;
+            pixels[i] = to_u8(color[0]);
+            pixels[i + 1] = to_u8(color[1]);
+            pixels[i + 2] = to_u8(color[2]);
+            pixels[i + 3] = 255;
+        }
+    }
+    pixels
+}
+
+pub const PREVIEW_SIZE: (u32, u32) = (256, 256);
+
+pub fn render_handle(grid: &VoxelGrid, camera: &OrbitCamera) -> image::Handle {
+    let (width, height) = PREVIEW_SIZE;
+    image::Handle::from_pixels(width, height, render(grid, camera, width, height))
+}
+
+/// One top- or bottom-facing quad from a [`Shape`]'s [`Shape::greedy_mesh`],
+/// in world XZ space. Side faces are axis-aligned planes perpendicular to
+/// the XZ plane, so they project to zero-area footprints and are skipped by
+/// [`shape_top_down_quads`]; every quad that remains is perfectly
+/// horizontal, so a single `y` (rather than a per-pixel depth test)
+/// describes its height.
+struct TopDownQuad {
+    min: [f32; 2],
+    max: [f32; 2],
+    y: f32,
+    color: [u8; 3],
+}
+
+fn shape_top_down_quads(shape: &Shape, palettes: &[Palette], out: &mut Vec<TopDownQuad>) {
+    let mesh = shape.greedy_mesh(palettes);
+    for (quad_i, &palette_index) in mesh.palette_indices.iter().enumerate() {
+        let face_indices = &mesh.indices[quad_i * 6..quad_i * 6 + 6];
+        let normal = mesh.normals[face_indices[0] as usize];
+        if normal[1].abs() < 0.5 {
+            continue;
+        }
+        let (mut min, mut max) = ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]);
+        let mut y = 0.;
+        for &index in face_indices {
+            let [x, vy, z] = mesh.positions[index as usize];
+            min[0] = min[0].min(x);
+            min[1] = min[1].min(z);
+            max[0] = max[0].max(x);
+            max[1] = max[1].max(z);
+            y = vy;
+        }
+        let material = palettes
+            .get(shape.palette as usize)
+            .and_then(|palette| palette.materials.get(palette_index as usize));
+        let [r, g, b, _a] = material.map_or([1.0, 1.0, 1.0, 1.0], |material| material.rgba.0);
+        out.push(TopDownQuad { min, max, y, color: [to_u8(r), to_u8(g), to_u8(b)] });
+    }
+}
+
+const THUMBNAIL_BACKGROUND: [u8; 4] = [13, 13, 20, 255];
+
+/// Rasterizes every [`Shape`] entity's top-down footprint into an RGBA8
+/// buffer, so a level can be picked out by eye before converting it. Quads
+/// are painted lowest-`y`-first (a painter's algorithm, not a real depth
+/// test), so higher surfaces correctly end up on top of whatever is beneath
+/// them.
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn render_scene_top_down(scene: &OwnedScene, width: u32, height: u32) -> Vec<u8> {
+    let mut quads = Vec::new();
+    for entity in scene.iter_entities() {
+        if let EntityKind::Shape(shape) = &entity.kind {
+            shape_top_down_quads(shape, &scene.palettes, &mut quads);
+        }
+    }
+    let mut pixels = vec![0_u8; (width * height * 4) as usize];
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.copy_from_slice(&THUMBNAIL_BACKGROUND);
+    }
+    if quads.is_empty() {
+        return pixels;
+    }
+    let (mut min_x, mut min_z, mut max_x, mut max_z) =
+        (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for quad in &quads {
+        min_x = min_x.min(quad.min[0]);
+        min_z = min_z.min(quad.min[1]);
+        max_x = max_x.max(quad.max[0]);
+        max_z = max_z.max(quad.max[1]);
+    }
+    // Letterbox into the largest axis's span so a long, thin level doesn't
+    // get stretched into a square image.
+    let span = (max_x - min_x).max(max_z - min_z).max(1e-3);
+    let center_x = (min_x + max_x) / 2.;
+    let center_z = (min_z + max_z) / 2.;
+    let world_to_pixel = |x: f32, z: f32| -> (f32, f32) {
+        (
+            (x - center_x) / span * width as f32 + width as f32 / 2.,
+            (center_z - z) / span * height as f32 + height as f32 / 2.,
+        )
+    };
+    quads.sort_by(|a, b| a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal));
+    for quad in &quads {
+        let (left, bottom) = world_to_pixel(quad.min[0], quad.min[1]);
+        let (right, top) = world_to_pixel(quad.max[0], quad.max[1]);
+        let (x_start, x_end) = (left.min(right), left.max(right));
+        let (y_start, y_end) = (top.min(bottom), top.max(bottom));
+        let x_start = x_start.floor().max(0.) as u32;
+        let x_end = x_end.ceil().min(width as f32) as u32;
+        let y_start = y_start.floor().max(0.) as u32;
+        let y_end = y_end.ceil().min(height as f32) as u32;
+        for y in y_start..y_end {
+            for x in x_start..x_end {
+                let i = ((y * width + x) * 4) as usize;
+                pixels[i] = quad.color[0];
+                pixels[i + 1] = quad.color[1];
+                pixels[i + 2] = quad.color[2];
+                pixels[i + 3] = 255;
+            }
+        }
+    }
+    pixels
+}
+
+pub const THUMBNAIL_SIZE: (u32, u32) = (192, 192);
+
+pub fn render_scene_top_down_handle(scene: &OwnedScene) -> image::Handle {
+    let (width, height) = THUMBNAIL_SIZE;
+    image::Handle::from_pixels(width, height, render_scene_top_down(scene, width, height))
+}
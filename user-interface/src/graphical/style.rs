@@ -1,83 +1,282 @@
+use std::{
+    fs, io,
+    time::{Duration, Instant},
+};
+
 use iced::{Background, Color, button, container, scrollable::{self, Scroller}};
+use serde::Deserialize;
+
+/// Colors and sizing for the level-list buttons, loaded from `config.toml`'s
+/// `[theme]` table by [`load_theme`]. [`Default`] matches the look that was
+/// previously hardcoded, so an absent or partial config file falls back to
+/// it field-by-field.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub base: [f32; 4],
+    pub border: [f32; 4],
+    pub highlight: [f32; 4],
+    pub text: [f32; 4],
+    pub text_highlight: [f32; 4],
+    pub loaded: [f32; 4],
+    pub font_size: u16,
+    pub selected_loaded_border_width: f32,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            base: [0., 0., 0., 1.],
+            border: [0., 0., 0., 1.],
+            highlight: [1., 1., 0., 1.],
+            text: [1., 1., 1., 1.],
+            text_highlight: [0., 0., 0., 1.],
+            loaded: [0.2, 0.2, 0., 1.],
+            font_size: 20,
+            selected_loaded_border_width: 2.,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Config {
+    theme: ThemeConfig,
+}
+
+/// Reads `config.toml`'s `[theme]` table from the OS config dir (e.g.
+/// `~/.config/teardown-utils/config.toml` on Linux), falling back to
+/// [`ThemeConfig::default`] if the dir can't be found, the file doesn't
+/// exist, or it can't be read or parsed. Read errors other than a missing
+/// file, and parse errors, are logged to stderr, same as other non-fatal
+/// load failures in this app.
+pub fn load_theme() -> ThemeConfig {
+    let path = match dirs::config_dir() {
+        Some(dir) => dir.join("teardown-utils").join("config.toml"),
+        None => return ThemeConfig::default(),
+    };
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return ThemeConfig::default(),
+        Err(err) => {
+            eprintln!("Could not read \"{}\": {}", path.display(), err);
+            return ThemeConfig::default();
+        }
+    };
+    match toml::from_str::<Config>(&contents) {
+        Ok(config) => config.theme,
+        Err(err) => {
+            eprintln!("Could not parse \"{}\": {}", path.display(), err);
+            ThemeConfig::default()
+        }
+    }
+}
+
+fn color(rgba: [f32; 4]) -> Color {
+    rgba.into()
+}
 
 pub struct Theme;
-pub struct LevelButton { pub selected: bool, pub loaded: bool }
+pub struct LevelButton { pub selected: bool, pub loaded: bool, pub theme: ThemeConfig }
 
-impl button::StyleSheet for LevelButton {
-    fn active(&self) -> button::Style {
-        match self {
-            Self { selected: false, loaded: false } => {
-                button::Style {
-                    background: Some(Background::Color([0., 0., 0.].into())),
-                    text_color: Color::from_rgb(1., 1., 1.),
-                    border_color: Color::from_rgb(0., 0., 0.),
-                    .. Default::default()
-                }
+fn terminal_style(theme: &ThemeConfig, selected: bool, loaded: bool) -> button::Style {
+    match (selected, loaded) {
+        (false, false) => {
+            button::Style {
+                background: Some(Background::Color(color(theme.base))),
+                text_color: color(theme.text),
+                border_color: color(theme.border),
+                .. Default::default()
             }
-            Self { selected: true, loaded: false } => {
-                button::Style {
-                    background: Some(Background::Color(Color::from_rgb(1., 1., 0.).into())),
-                    text_color: Color::from_rgb(0., 0., 0.),
-                    border_color: Color::from_rgb(0., 0., 0.),
-                    .. Default::default()
-                }
-
+        }
+        (true, false) => {
+            button::Style {
+                background: Some(Background::Color(color(theme.highlight))),
+                text_color: color(theme.text_highlight),
+                border_color: color(theme.border),
+                .. Default::default()
             }
-            Self { selected: true, loaded: true } => {
-                button::Style {
-                    background: Some(Background::Color(Color::from_rgb(0.2, 0.2, 0.).into())),
-                    text_color: Color::from_rgb(1., 1., 1.),
-                    border_width: 2.,
-                    border_color: Color::from_rgb(1., 1., 0.),
-                    .. Default::default()
-                }
+
+        }
+        (true, true) => {
+            button::Style {
+                background: Some(Background::Color(color(theme.loaded))),
+                text_color: color(theme.text),
+                border_width: theme.selected_loaded_border_width,
+                border_color: color(theme.highlight),
+                .. Default::default()
             }
-            Self { selected: false, loaded: true } => {
-                button::Style {
-                    background: Some(Background::Color([0.2, 0.2, 0.].into())),
-                    text_color: Color::from_rgb(1., 1., 1.),
-                    border_color: Color::from_rgb(0., 0., 0.),
-                    .. Default::default()
-                }
+        }
+        (false, true) => {
+            button::Style {
+                background: Some(Background::Color(color(theme.loaded))),
+                text_color: color(theme.text),
+                border_color: color(theme.border),
+                .. Default::default()
             }
         }
     }
+}
+
+impl button::StyleSheet for LevelButton {
+    fn active(&self) -> button::Style {
+        terminal_style(&self.theme, self.selected, self.loaded)
+    }
 
     fn hovered(&self) -> button::Style {
-        let active = self.active();
-        button::Style {
-            border_color: Color {
-                r: active.border_color.r + 0.2,
-                g: active.border_color.g + 0.2,
-                b: active.border_color.b,
-                a: active.border_color.a
-            },
-            border_width: 2.,
-            .. active
-        }
+        hovered_from(self.active())
     }
 
     fn pressed(&self) -> button::Style {
-        let active = self.active();
-        button::Style {
-            background: active.background.map(|background| match background {
-                Background::Color(back) => Background::Color(Color {
-                    r: back.r - 0.2,
-                    g: back.g - 0.2,
-                    b: back.b - 0.2,
-                    a: back.a
-                })
-            }),
-            border_width: 2.,
-            border_color: Color {
-                r: active.border_color.r + 0.4,
-                g: active.border_color.g + 0.4,
-                b: active.border_color.b,
-                a: active.border_color.a
-            },
-            .. active
+        pressed_from(self.active())
+    }
+}
+
+fn hovered_from(active: button::Style) -> button::Style {
+    button::Style {
+        border_color: Color {
+            r: active.border_color.r + 0.2,
+            g: active.border_color.g + 0.2,
+            b: active.border_color.b,
+            a: active.border_color.a
+        },
+        border_width: 2.,
+        .. active
+    }
+}
+
+fn pressed_from(active: button::Style) -> button::Style {
+    button::Style {
+        background: active.background.map(|background| match background {
+            Background::Color(back) => Background::Color(Color {
+                r: back.r - 0.2,
+                g: back.g - 0.2,
+                b: back.b - 0.2,
+                a: back.a
+            })
+        }),
+        border_width: 2.,
+        border_color: Color {
+            r: active.border_color.r + 0.4,
+            g: active.border_color.g + 0.4,
+            b: active.border_color.b,
+            a: active.border_color.a
+        },
+        .. active
+    }
+}
+
+const ANIMATION_DURATION: Duration = Duration::from_millis(150);
+
+fn srgb_channel_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_channel_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+/// Cubic ease-in-out, so the animation starts and ends gently.
+fn ease(t: f32) -> f32 { t * t * (3. - 2. * t) }
+
+/// Linearly interpolates between two sRGB colors by first converting them to
+/// linear light, so the midpoint doesn't look muddy the way interpolating
+/// sRGB directly would. Alpha is lerped directly, since it isn't subject to
+/// gamma correction.
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let lerp_channel = |from: f32, to: f32| {
+        let from = srgb_channel_to_linear(from);
+        let to = srgb_channel_to_linear(to);
+        linear_channel_to_srgb(from + (to - from) * t)
+    };
+    Color {
+        r: lerp_channel(from.r, to.r),
+        g: lerp_channel(from.g, to.g),
+        b: lerp_channel(from.b, to.b),
+        a: from.a + (to.a - from.a) * t,
+    }
+}
+
+fn lerp_style(from: &button::Style, to: &button::Style, t: f32) -> button::Style {
+    let lerp_background = |from: Option<Background>, to: Option<Background>| match (from, to) {
+        (Some(Background::Color(from)), Some(Background::Color(to))) => {
+            Some(Background::Color(lerp_color(from, to, t)))
+        }
+        (_, to) => to,
+    };
+    button::Style {
+        background: lerp_background(from.background, to.background),
+        border_color: lerp_color(from.border_color, to.border_color, t),
+        border_width: from.border_width + (to.border_width - from.border_width) * t,
+        text_color: lerp_color(from.text_color, to.text_color, t),
+        .. *to
+    }
+}
+
+/// Per-button animation state for [`LevelButton`], easing between the four
+/// terminal `selected`/`loaded` styles instead of snapping between them.
+/// Stored on each `Level` and kept across renders; call [`Self::set_target`]
+/// with the button's current `selected`/`loaded` state every view, then
+/// [`Self::advance`] to get the (possibly still-animating) style to use.
+pub struct LevelButtonAnimation {
+    target: (bool, bool),
+    from: button::Style,
+    to: button::Style,
+    start: Instant,
+}
+
+impl LevelButtonAnimation {
+    pub fn new(theme: &ThemeConfig, selected: bool, loaded: bool) -> Self {
+        let style = terminal_style(theme, selected, loaded);
+        Self {
+            target: (selected, loaded),
+            from: style,
+            to: style,
+            start: Instant::now() - ANIMATION_DURATION,
+        }
+    }
+
+    /// Begins a new transition if `selected`/`loaded` differs from the
+    /// currently-targeted state, starting from wherever the animation
+    /// currently is (so a second state change mid-transition eases
+    /// smoothly instead of jumping back to a terminal style first).
+    pub fn set_target(&mut self, theme: &ThemeConfig, selected: bool, loaded: bool) {
+        let target = (selected, loaded);
+        if target != self.target {
+            self.from = self.advance(Instant::now());
+            self.to = terminal_style(theme, selected, loaded);
+            self.target = target;
+            self.start = Instant::now();
         }
     }
+
+    /// Advances the animation to `now` and returns the interpolated style.
+    /// The GUI update loop should call this (via a periodic tick, or simply
+    /// on every redraw) to drive the interpolation forward.
+    pub fn advance(&mut self, now: Instant) -> button::Style {
+        let t = now.saturating_duration_since(self.start).as_secs_f32()
+            / ANIMATION_DURATION.as_secs_f32();
+        lerp_style(&self.from, &self.to, ease(t.clamp(0., 1.)))
+    }
+}
+
+/// A `button::StyleSheet` that returns a precomputed, already-interpolated
+/// style, so [`LevelButtonAnimation::advance`]'s result can be handed to
+/// `Button::style` the same way [`LevelButton`] is.
+pub struct AnimatedButton(pub button::Style);
+
+impl button::StyleSheet for AnimatedButton {
+    fn active(&self) -> button::Style {
+        self.0
+    }
+
+    fn hovered(&self) -> button::Style {
+        hovered_from(self.0)
+    }
+
+    fn pressed(&self) -> button::Style {
+        pressed_from(self.0)
+    }
 }
 
 impl button::StyleSheet for Theme {
@@ -0,0 +1,73 @@
+//! Watches the level `.bin` directory and the quicksave directory for
+//! filesystem changes, so [`MainView`](super::MainView) can pick up levels
+//! dropped in or overwritten by the game without the user restarting the
+//! app. Runs the [`notify`] watcher on its own OS thread (it blocks on
+//! `recv`) and forwards matches as an iced [`Subscription`].
+
+use std::{
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::mpsc,
+    time::Duration,
+};
+
+use iced::Subscription;
+use iced_native::subscription::Recipe;
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
+
+use super::MainMessage;
+
+pub(crate) fn watch(paths: Vec<PathBuf>) -> Subscription<MainMessage> {
+    Subscription::from_recipe(Watch { paths })
+}
+
+struct Watch {
+    paths: Vec<PathBuf>,
+}
+
+impl<H: Hasher, I> Recipe<H, I> for Watch {
+    type Output = MainMessage;
+
+    fn hash(&self, state: &mut H) {
+        std::any::TypeId::of::<Self>().hash(state);
+        self.paths.hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: iced_futures::BoxStream<I>,
+    ) -> iced_futures::BoxStream<Self::Output> {
+        let (event_tx, event_rx) = iced_futures::futures::channel::mpsc::unbounded();
+        let paths = self.paths;
+        std::thread::spawn(move || {
+            let (tx, rx) = mpsc::channel();
+            let mut watcher = match notify::watcher(tx, Duration::from_millis(500)) {
+                Ok(watcher) => watcher,
+                Err(err) => {
+                    eprintln!("Could not start the level file watcher: {}", err);
+                    return;
+                }
+            };
+            for path in &paths {
+                if let Err(err) = watcher.watch(path, RecursiveMode::NonRecursive) {
+                    eprintln!("Could not watch \"{}\": {}", path.display(), err);
+                }
+            }
+            for event in rx {
+                let path = match event {
+                    DebouncedEvent::Create(path)
+                    | DebouncedEvent::Write(path)
+                    | DebouncedEvent::Remove(path)
+                    | DebouncedEvent::Rename(_, path) => Some(path),
+                    _ => None,
+                };
+                if let Some(path) = path {
+                    if event_tx.unbounded_send(MainMessage::FileChanged(path)).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Box::pin(event_rx)
+    }
+}